@@ -33,16 +33,25 @@ impl AssetManager {
     }
 
     pub fn load_assets(&mut self, context: &mut Context) {
-        self.load_all_assets_in_dir(context, &RESOURCES_DIR);
+        self.load_all_assets_in_dir(Some(context), &RESOURCES_DIR);
         log::info!("Loaded {} files", self.textures.len() + self.jsons.len());
     }
 
-    fn load_all_assets_in_dir(&mut self, context: &mut Context, dir: &Dir) {
+    // Loads only JSON files, so it doesn't need a graphics context.
+    // Requested textures will be reported as missing.
+    pub fn load_json_assets(&mut self) {
+        self.load_all_assets_in_dir(None, &RESOURCES_DIR);
+        log::info!("Loaded {} files", self.jsons.len());
+    }
+
+    fn load_all_assets_in_dir(&mut self, mut context: Option<&mut Context>, dir: &Dir) {
         for file in dir.files() {
             match file.path().extension() {
                 Some(ext) => match ext.to_str().unwrap() {
                     "png" => {
-                        self.load_texture(context, file);
+                        if let Some(context) = context.as_deref_mut() {
+                            self.load_texture(context, file);
+                        }
                     }
                     "json" => {
                         self.load_json(file);
@@ -58,7 +67,7 @@ impl AssetManager {
         }
 
         for dir in dir.dirs() {
-            self.load_all_assets_in_dir(context, dir);
+            self.load_all_assets_in_dir(context.as_deref_mut(), dir);
         }
     }
 
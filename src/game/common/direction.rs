@@ -39,4 +39,55 @@ impl Direction {
             Direction::None => Direction::None,
         }
     }
+
+    pub fn rotate_clockwise(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+            Direction::None => Direction::None,
+        }
+    }
+
+    // Quarter turns clockwise from Up.
+    fn get_turns(self) -> u32 {
+        match self {
+            Direction::Up | Direction::None => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    // Turns direction given relatively to the Up facing into the absolute one.
+    pub fn rotated_by(self, facing: Direction) -> Direction {
+        (0..facing.get_turns()).fold(self, |dir, _| dir.rotate_clockwise())
+    }
+
+    // Turns absolute direction into the one relative to the facing.
+    pub fn unrotated_by(self, facing: Direction) -> Direction {
+        (0..(4 - facing.get_turns()) % 4).fold(self, |dir, _| dir.rotate_clockwise())
+    }
+
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right",
+            Direction::None => "none",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Direction> {
+        match name {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            "none" => Some(Direction::None),
+            _ => None,
+        }
+    }
 }
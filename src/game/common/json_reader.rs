@@ -1,3 +1,5 @@
+use crate::game::common::math::{IVec2, Vec2};
+
 pub struct JsonReader {}
 
 impl JsonReader {
@@ -39,6 +41,28 @@ impl JsonReader {
         }
     }
 
+    pub fn read_u32(obj: &serde_json::Value, name: &str, error: &mut bool) -> u32 {
+        match obj {
+            serde_json::Value::Object(obj) => match obj.get(name) {
+                Some(serde_json::Value::Number(n)) => match n.as_u64() {
+                    Some(n_u64) if n_u64 <= u32::MAX as u64 => n_u64 as u32,
+                    _ => {
+                        *error = true;
+                        0
+                    }
+                },
+                _ => {
+                    *error = true;
+                    0
+                }
+            },
+            _ => {
+                *error = true;
+                0
+            }
+        }
+    }
+
     pub fn read_f32(obj: &serde_json::Value, name: &str, error: &mut bool) -> f32 {
         match obj {
             serde_json::Value::Object(obj) => match obj.get(name) {
@@ -99,4 +123,36 @@ impl JsonReader {
             }
         }
     }
+
+    pub fn read_bool(obj: &serde_json::Value, name: &str, error: &mut bool) -> bool {
+        match obj {
+            serde_json::Value::Object(obj) => match obj.get(name) {
+                Some(serde_json::Value::Bool(b)) => *b,
+                _ => {
+                    *error = true;
+                    false
+                }
+            },
+            _ => {
+                *error = true;
+                false
+            }
+        }
+    }
+
+    pub fn read_vec2(obj: &serde_json::Value, name: &str, error: &mut bool) -> Vec2 {
+        let vec = Self::read_obj(obj, name, error);
+        Vec2::new(
+            Self::read_f32(&vec, "x", error),
+            Self::read_f32(&vec, "y", error),
+        )
+    }
+
+    pub fn read_ivec2(obj: &serde_json::Value, name: &str, error: &mut bool) -> IVec2 {
+        let vec = Self::read_obj(obj, name, error);
+        IVec2::new(
+            Self::read_i32(&vec, "x", error) as isize,
+            Self::read_i32(&vec, "y", error) as isize,
+        )
+    }
 }
@@ -0,0 +1,15 @@
+use serde_json::json;
+
+use crate::game::common::math::{IVec2, Vec2};
+
+pub struct JsonWriter {}
+
+impl JsonWriter {
+    pub fn write_vec2(vec: Vec2) -> serde_json::Value {
+        json!({ "x": vec.x, "y": vec.y })
+    }
+
+    pub fn write_ivec2(vec: IVec2) -> serde_json::Value {
+        json!({ "x": vec.x, "y": vec.y })
+    }
+}
@@ -138,6 +138,11 @@ impl Vec2 {
         IVec2::new(self.x as isize, self.y as isize)
     }
 
+    // Cell which center is the closest to the point.
+    pub fn to_ivec2_rounded(self) -> IVec2 {
+        IVec2::new(self.x.round() as isize, self.y.round() as isize)
+    }
+
     pub fn sqr_length(self) -> f32 {
         self.dot(self)
     }
@@ -1,5 +1,7 @@
 pub mod asset_manager;
 pub mod direction;
 pub mod json_reader;
+pub mod json_writer;
 pub mod logger;
 pub mod math;
+pub mod noise;
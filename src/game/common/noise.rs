@@ -0,0 +1,50 @@
+use crate::game::common::math::Vec2;
+
+// Deterministic value noise: depends only on the seed and the position,
+// so chunks may be generated in any order.
+pub struct Noise {
+    seed: u64,
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Noise {
+        Noise { seed }
+    }
+
+    // Smoothly interpolated random values on a grid with cell size of scale, in [0, 1).
+    pub fn sample(&self, position: Vec2, scale: f32) -> f32 {
+        let x = position.x / scale;
+        let y = position.y / scale;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let tx = Self::smooth(x - x0);
+        let ty = Self::smooth(y - y0);
+
+        let (ix, iy) = (x0 as i64, y0 as i64);
+        let v00 = self.lattice_value(ix, iy);
+        let v10 = self.lattice_value(ix + 1, iy);
+        let v01 = self.lattice_value(ix, iy + 1);
+        let v11 = self.lattice_value(ix + 1, iy + 1);
+
+        let v0 = v00 + (v10 - v00) * tx;
+        let v1 = v01 + (v11 - v01) * tx;
+        v0 + (v1 - v0) * ty
+    }
+
+    fn smooth(t: f32) -> f32 {
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    fn lattice_value(&self, x: i64, y: i64) -> f32 {
+        let hash = Self::mix(self.seed ^ Self::mix(x as u64 ^ Self::mix(y as u64)));
+        (hash >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // SplitMix64 finalizer.
+    fn mix(mut value: u64) -> u64 {
+        value = value.wrapping_add(0x9e3779b97f4a7c15);
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+        value ^ (value >> 31)
+    }
+}
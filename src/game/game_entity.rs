@@ -4,6 +4,7 @@ pub use crate::game::renderer::SpriteTransform;
 pub struct UpdateParameters {
     pub delta_time: f32,
     pub from_last_tick: f32,
+    pub tick_period: f32,
     pub last_tick_id: u32,
 }
 
@@ -188,6 +188,16 @@ impl Gui {
         self.mouse_state.wheel_h = 0.0;
     }
 
+    // Clicks over windows shouldn't reach the world.
+    pub fn wants_mouse(&self) -> bool {
+        self.imgui.io().want_capture_mouse
+    }
+
+    // Keys typed into inputs shouldn't reach the world.
+    pub fn wants_keyboard(&self) -> bool {
+        self.imgui.io().want_capture_keyboard
+    }
+
     pub fn update_mouse_pos(&mut self, x: f32, y: f32) {
         self.mouse_state.pos = (x as i32, y as i32);
     }
@@ -0,0 +1,73 @@
+use crate::game::common::math::{IVec2, Vec2};
+
+use super::field::Field;
+
+// Topmost thing under the cursor.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Hover {
+    // Cell of the stack, there's a single one per cell.
+    LayingObject(IVec2),
+    // Origin cell of the building.
+    Building(IVec2),
+    Cell(IVec2),
+}
+
+// Pointer state shared by the world and gui layers.
+pub struct Cursor {
+    // In world coordinates.
+    position: Vec2,
+    hover: Option<Hover>,
+    // Origin cell of the selected building.
+    selected_building: Option<IVec2>,
+}
+
+impl Cursor {
+    pub fn new() -> Cursor {
+        Cursor {
+            position: Vec2::zero(),
+            hover: None,
+            selected_building: None,
+        }
+    }
+
+    pub fn get_position(&self) -> Vec2 {
+        self.position
+    }
+
+    pub fn get_cell_position(&self) -> IVec2 {
+        Field::get_cell_position_at(self.position)
+    }
+
+    pub fn get_hover(&self) -> Option<Hover> {
+        self.hover
+    }
+
+    pub fn get_selected_building(&self) -> Option<IVec2> {
+        self.selected_building
+    }
+
+    // Hover is picked again, as the world under the cursor changes even if it doesn't move.
+    pub fn update(&mut self, position: Vec2, field: &Field) {
+        self.position = position;
+        self.hover = Some(field.pick(position));
+
+        // Selected building may be deconstructed or replaced.
+        if let Some(origin) = self.selected_building {
+            if field.get_building_origin(origin) != Some(origin) {
+                self.selected_building = None;
+            }
+        }
+    }
+
+    // Selects the hovered building, anything else clears the selection.
+    pub fn select_hovered(&mut self) {
+        self.selected_building = match self.hover {
+            Some(Hover::Building(origin)) => Some(origin),
+            _ => None,
+        };
+    }
+
+    pub fn clear_selection(&mut self) {
+        self.selected_building = None;
+    }
+}
@@ -0,0 +1,167 @@
+use crate::game::{
+    common::math::IVec2, gui::with_gui::WithGui, location::field::building::item::Item,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum GameEventKind {
+    BuildingPlaced,
+    BuildingRemoved,
+    ItemProduced,
+    RecipeCompleted,
+    PowerShortage,
+    PlayerEnteredTrigger,
+    TechnologyResearched,
+}
+
+// Something that happened in the location, for the systems that don't take part in
+// the simulation, e.g. statistics or notifications.
+#[derive(Clone)]
+pub enum GameEvent {
+    // By the player, buildings of scenarios and saves aren't reported.
+    BuildingPlaced {
+        name: String,
+        origin: IVec2,
+    },
+    BuildingRemoved {
+        name: String,
+        origin: IVec2,
+    },
+    ItemProduced {
+        item: Item,
+        amount: usize,
+        origin: IVec2,
+    },
+    RecipeCompleted {
+        recipe: String,
+        origin: IVec2,
+    },
+    // Network that didn't have a shortage the tick before.
+    PowerShortage {
+        voltage: u32,
+        production: u32,
+        consumption: u32,
+    },
+    PlayerEnteredTrigger {
+        name: String,
+    },
+    TechnologyResearched {
+        name: String,
+    },
+}
+
+impl GameEvent {
+    pub fn get_kind(&self) -> GameEventKind {
+        match self {
+            GameEvent::BuildingPlaced { .. } => GameEventKind::BuildingPlaced,
+            GameEvent::BuildingRemoved { .. } => GameEventKind::BuildingRemoved,
+            GameEvent::ItemProduced { .. } => GameEventKind::ItemProduced,
+            GameEvent::RecipeCompleted { .. } => GameEventKind::RecipeCompleted,
+            GameEvent::PowerShortage { .. } => GameEventKind::PowerShortage,
+            GameEvent::PlayerEnteredTrigger { .. } => GameEventKind::PlayerEnteredTrigger,
+            GameEvent::TechnologyResearched { .. } => GameEventKind::TechnologyResearched,
+        }
+    }
+
+    pub fn get_description(&self) -> String {
+        match self {
+            GameEvent::BuildingPlaced { name, origin } => format!("{} placed at {}", name, origin),
+            GameEvent::BuildingRemoved { name, origin } => {
+                format!("{} removed from {}", name, origin)
+            }
+            GameEvent::ItemProduced {
+                item,
+                amount,
+                origin,
+            } => format!("{} x{} produced at {}", item.get_name(), amount, origin),
+            GameEvent::RecipeCompleted { recipe, origin } => {
+                format!("{} completed at {}", recipe, origin)
+            }
+            GameEvent::PowerShortage {
+                voltage,
+                production,
+                consumption,
+            } => format!(
+                "{}V network is short of power: {}/{}",
+                voltage, production, consumption
+            ),
+            GameEvent::PlayerEnteredTrigger { name } => format!("entered {}", name),
+            GameEvent::TechnologyResearched { name } => format!("{} is researched", name),
+        }
+    }
+}
+
+// Raised by a building, the field adds the building origin.
+pub enum BuildingEvent {
+    ItemProduced { item: Item, amount: usize },
+    RecipeCompleted { recipe: String },
+}
+
+impl BuildingEvent {
+    pub fn at(self, origin: IVec2) -> GameEvent {
+        match self {
+            BuildingEvent::ItemProduced { item, amount } => GameEvent::ItemProduced {
+                item,
+                amount,
+                origin,
+            },
+            BuildingEvent::RecipeCompleted { recipe } => {
+                GameEvent::RecipeCompleted { recipe, origin }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SubscriberId(usize);
+
+// System reacting to the events, subscribes to the bus on creation.
+pub trait EventSubscriber: WithGui {
+    // Key of the saved state.
+    fn get_name(&self) -> &str;
+
+    // Takes the events queued for the subscriber.
+    fn handle_events(&mut self, event_bus: &mut EventBus);
+
+    // None if the subscriber isn't saved.
+    fn save_state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    fn load_state(&mut self, _state: &serde_json::Value, _error: &mut bool) {}
+}
+
+struct Subscriber {
+    kinds: Vec<GameEventKind>,
+    // Not taken yet, in order of emitting.
+    queue: Vec<GameEvent>,
+}
+
+// Events are queued for every subscriber of their kind until it takes them,
+// so the emitters don't know who listens.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Vec<Subscriber>,
+}
+
+impl EventBus {
+    pub fn subscribe(&mut self, kinds: &[GameEventKind]) -> SubscriberId {
+        self.subscribers.push(Subscriber {
+            kinds: kinds.to_vec(),
+            queue: vec![],
+        });
+        SubscriberId(self.subscribers.len() - 1)
+    }
+
+    pub fn emit(&mut self, event: GameEvent) {
+        let kind = event.get_kind();
+        for subscriber in self.subscribers.iter_mut() {
+            if subscriber.kinds.contains(&kind) {
+                subscriber.queue.push(event.clone());
+            }
+        }
+    }
+
+    pub fn take_events(&mut self, id: SubscriberId) -> Vec<GameEvent> {
+        std::mem::take(&mut self.subscribers[id.0].queue)
+    }
+}
@@ -0,0 +1,464 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use super::{item::*, item_sides::ItemSides};
+use crate::game::{
+    common::{direction::Direction, json_reader::JsonReader, math::Vec2},
+    game_entity::GameEntity,
+    gui::with_gui::*,
+    location::field::building::{Building, BuildingClone},
+    message::*,
+    Renderer, SpriteTransform, UpdateParameters,
+};
+
+#[derive(Clone)]
+enum Routing {
+    // Alternates the output tried first for each item.
+    Splitter {
+        next_output: usize,
+    },
+    // Takes the items from the inputs in the order of "input_sides".
+    Merger,
+    // Chosen item goes to the side, the rest to the other outputs.
+    Filter {
+        side: Direction,
+        item: Option<Item>,
+        // Sorted by name.
+        choices: Vec<Item>,
+    },
+}
+
+// Belt building passing the items through its center, declared with a "type" in transport_belts.json.
+pub struct BeltRouter {
+    name: String,
+    facing: Direction,
+    item_sides: ItemSides,
+    routing: Routing,
+
+    // Keyed by the side relative to the Up facing, so that turning keeps the items in place.
+    input_slots: HashMap<Direction, Option<TransportedItem>>,
+    // Item waiting in the center to be pushed out.
+    center_slot: Option<TransportedItem>,
+}
+
+impl BeltRouter {
+    pub fn from_json_object(obj: &serde_json::Value, item_factory: &ItemFactory) -> BeltRouter {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let item_sides = ItemSides::from_json_object(obj, &mut error);
+        let outputs = item_sides.get_output_directions(Direction::Up);
+        let routing = match JsonReader::read_string(obj, "type", &mut error).as_str() {
+            "splitter" => Routing::Splitter { next_output: 0 },
+            "merger" => Routing::Merger,
+            "filter" => {
+                let side =
+                    Direction::from_name(&JsonReader::read_string(obj, "filter_side", &mut error))
+                        .unwrap_or(Direction::None);
+                if !outputs.contains(&side) {
+                    error = true;
+                }
+                Routing::Filter {
+                    side,
+                    item: None,
+                    choices: item_factory.get_sorted_items(),
+                }
+            }
+            _ => {
+                error = true;
+                Routing::Merger
+            }
+        };
+        if outputs.is_empty() {
+            error = true;
+        }
+
+        if error {
+            log::error!(
+                "Failed to parse BeltRouter from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("BeltRouter succesfully loaded({})", name);
+        }
+
+        BeltRouter::new(name, item_sides, routing)
+    }
+
+    fn new(name: String, item_sides: ItemSides, routing: Routing) -> BeltRouter {
+        let input_slots = item_sides
+            .get_input_directions(Direction::Up)
+            .into_iter()
+            .map(|side| (side, None))
+            .collect();
+
+        BeltRouter {
+            name,
+            facing: Direction::Up,
+            item_sides,
+            routing,
+            input_slots,
+            center_slot: None,
+        }
+    }
+
+    // Input slots lie halfway to the edge of their side.
+    fn get_slot_position(&self, side: Direction) -> Vec2 {
+        side.rotated_by(self.facing).to_ivec2().to_vec2() * 0.25
+    }
+
+    fn get_edge_position(direction: Direction) -> Vec2 {
+        direction.to_ivec2().to_vec2() * 0.5
+    }
+
+    // Absolute directions to push the item to, pushing is tried from the last one.
+    fn get_target_directions(&mut self, item_id: ItemId) -> Vec<Direction> {
+        let mut outputs = self.item_sides.get_output_directions(self.facing);
+        match &mut self.routing {
+            Routing::Splitter { next_output } => {
+                let count = outputs.len();
+                if count > 0 {
+                    *next_output %= count;
+                    outputs.rotate_left((*next_output + 1) % count);
+                    *next_output = (*next_output + 1) % count;
+                }
+                outputs
+            }
+            Routing::Merger => outputs,
+            Routing::Filter { side, item, .. } => {
+                let side = side.rotated_by(self.facing);
+                if item.as_ref().map(|item| item.get_id()) == Some(item_id) {
+                    vec![side]
+                } else {
+                    outputs.retain(|&dir| dir != side);
+                    outputs
+                }
+            }
+        }
+    }
+
+    fn try_push_item(
+        &mut self,
+        mut item: TransportedItem,
+        direction: Direction,
+        tick_id: u32,
+    ) -> Option<TransportedItem> {
+        if item.last_tick_moved == tick_id {
+            return Some(item);
+        }
+
+        let side = direction.unrotated_by(self.facing);
+        let position = self.get_slot_position(side);
+        match self.input_slots.get_mut(&side) {
+            Some(slot) if slot.is_none() => {
+                item.set_movement(Self::get_edge_position(direction), position, tick_id);
+                item.last_tick_moved = tick_id;
+                *slot = Some(item);
+                None
+            }
+            _ => Some(item),
+        }
+    }
+
+    // Moves an item from the first filled input to the center.
+    fn move_to_center(&mut self, tick_id: u32) {
+        if self.center_slot.is_some() {
+            return;
+        }
+
+        for side in self.get_input_sides() {
+            let position = self.get_slot_position(side);
+            let slot = self.input_slots.get_mut(&side).unwrap();
+            if matches!(slot, Some(item) if item.last_tick_moved != tick_id) {
+                let mut item = slot.take().unwrap();
+                item.last_tick_moved = tick_id;
+                item.set_movement(position, Vec2::zero(), tick_id);
+                self.center_slot = Some(item);
+                return;
+            }
+        }
+    }
+
+    fn pull_item_failed(&mut self, mut item: TransportedItem, tick_id: u32) {
+        item.set_movement(Vec2::zero(), Vec2::zero(), tick_id);
+        self.center_slot = Some(item);
+    }
+
+    fn load_slot(
+        saved_slot: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) -> Option<TransportedItem> {
+        match saved_slot {
+            serde_json::Value::Null => None,
+            saved_item => Some(TransportedItem::from_saved_state(
+                saved_item,
+                item_factory,
+                error,
+            )),
+        }
+    }
+
+    // In the order of "input_sides", relative to the Up facing.
+    fn get_input_sides(&self) -> Vec<Direction> {
+        self.item_sides.get_input_directions(Direction::Up)
+    }
+}
+
+impl GameEntity for BeltRouter {
+    fn update(&mut self, parameters: &UpdateParameters) {
+        for item in self
+            .input_slots
+            .values_mut()
+            .chain(std::iter::once(&mut self.center_slot))
+            .flatten()
+        {
+            item.update(parameters);
+        }
+    }
+
+    fn tick(&mut self, tick_id: u32) {
+        self.move_to_center(tick_id);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        for item in self
+            .input_slots
+            .values_mut()
+            .chain(std::iter::once(&mut self.center_slot))
+            .flatten()
+        {
+            item.render(renderer, transform.clone());
+        }
+    }
+}
+
+impl WithGui for BeltRouter {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        let slot_name = |slot: &Option<TransportedItem>| match slot {
+            Some(item) => String::from(item.get_item().get_name()),
+            None => String::from("-"),
+        };
+        for side in self.get_input_sides() {
+            params.ui.text(format!(
+                "in {}: {}",
+                side.rotated_by(self.facing).get_name(),
+                slot_name(&self.input_slots[&side])
+            ));
+        }
+        params
+            .ui
+            .text(format!("center: {}", slot_name(&self.center_slot)));
+
+        if let Routing::Filter { item, choices, .. } = &mut self.routing {
+            params.ui.separator();
+            params.ui.text("filter:");
+            for choice in choices.iter() {
+                let is_current = item.as_ref().map(|item| item.get_id()) == Some(choice.get_id());
+                if imgui::Selectable::new(choice.get_name())
+                    .selected(is_current)
+                    .build(params.ui)
+                {
+                    *item = if is_current {
+                        None
+                    } else {
+                        Some(choice.clone())
+                    };
+                }
+            }
+        }
+    }
+}
+
+impl BuildingClone for BeltRouter {
+    fn clone_box(&self) -> Box<dyn Building> {
+        Box::from(BeltRouter::new(
+            self.name.clone(),
+            self.item_sides.clone(),
+            self.routing.clone(),
+        ))
+    }
+}
+
+impl Building for BeltRouter {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        stack_transported_items(
+            self.input_slots
+                .values()
+                .chain(std::iter::once(&self.center_slot))
+                .flatten(),
+        )
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+
+        for side in self.get_input_sides() {
+            let position = self.get_slot_position(side);
+            if let Some(item) = self.input_slots.get_mut(&side).unwrap().as_mut() {
+                item.set_movement(position, position, item.last_tick_moved);
+            }
+        }
+        if let Some(item) = self.center_slot.as_mut() {
+            item.set_movement(Vec2::zero(), Vec2::zero(), item.last_tick_moved);
+        }
+    }
+
+    // Filter takes the "item" to route to its side.
+    fn load_config(&mut self, config: &serde_json::Value, error: &mut bool) {
+        match &mut self.routing {
+            Routing::Filter { item, choices, .. } => {
+                let item_name = JsonReader::read_string(config, "item", error);
+                *item = choices
+                    .iter()
+                    .find(|choice| choice.get_name() == item_name)
+                    .cloned();
+                if item.is_none() {
+                    log::error!("There's no such item {} to filter", item_name);
+                    *error = true;
+                }
+            }
+            _ => {
+                log::error!("Building {} can't be configured", self.name);
+                *error = true;
+            }
+        }
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        let save_slot = |slot: &Option<TransportedItem>| match slot {
+            Some(item) => item.save_state(),
+            None => serde_json::Value::Null,
+        };
+
+        let mut input_slots = serde_json::Map::new();
+        for side in self.get_input_sides() {
+            input_slots.insert(
+                String::from(side.get_name()),
+                save_slot(&self.input_slots[&side]),
+            );
+        }
+
+        let mut state = json!({
+            "input_slots": input_slots,
+            "center_slot": save_slot(&self.center_slot),
+        });
+        match &self.routing {
+            Routing::Splitter { next_output } => state["next_output"] = json!(next_output),
+            Routing::Merger => {}
+            Routing::Filter { item, .. } => {
+                state["filter_item"] = json!(item.as_ref().map(|item| item.get_name()))
+            }
+        }
+        state
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        let saved_slots = JsonReader::read_obj(state, "input_slots", error);
+        for side in self.get_input_sides() {
+            let slot = match saved_slots.get(side.get_name()) {
+                Some(saved_slot) => Self::load_slot(saved_slot, item_factory, error),
+                None => {
+                    *error = true;
+                    None
+                }
+            };
+            self.input_slots.insert(side, slot);
+        }
+        self.center_slot = match state.get("center_slot") {
+            Some(saved_slot) => Self::load_slot(saved_slot, item_factory, error),
+            None => {
+                *error = true;
+                None
+            }
+        };
+
+        match &mut self.routing {
+            Routing::Splitter { next_output } => {
+                *next_output = JsonReader::read_u32(state, "next_output", error) as usize
+            }
+            Routing::Merger => {}
+            Routing::Filter { item, .. } => {
+                *item = match state.get("filter_item") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(_) => {
+                        let item_name = JsonReader::read_string(state, "filter_item", error);
+                        Some(item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name)))
+                    }
+                };
+            }
+        }
+    }
+}
+
+impl MessageSender for BeltRouter {
+    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        let mut item = match self.center_slot.take() {
+            Some(item) if item.last_tick_moved != tick_id => item,
+            item => {
+                self.center_slot = item;
+                return vec![];
+            }
+        };
+
+        let directions = self.get_target_directions(item.get_id());
+        if let Some(&direction) = directions.last() {
+            item.set_movement(Vec2::zero(), Self::get_edge_position(direction), tick_id);
+        }
+        vec![Message::FieldMessage(field_message::Message {
+            id: 0,
+            sender: field_message::MessageExchangeActor::default(),
+            receiver: field_message::MessageExchangeActor::default(),
+            target: field_message::Target::Directions(directions),
+            tick_id,
+            refund: false,
+            ground_cell: None,
+            body: field_message::MessageBody::PushItem(item),
+        })]
+    }
+}
+
+impl MessageReceiver for BeltRouter {
+    fn try_push_message(&mut self, mut message: Message) -> Option<Message> {
+        match message {
+            Message::FieldMessage(ref mut msg) => match &msg.body {
+                field_message::MessageBody::PushItem(item) => {
+                    let item = item.clone();
+                    if msg.refund {
+                        self.pull_item_failed(item, msg.tick_id);
+                        return None;
+                    }
+
+                    let direction = Direction::from_ivec2(
+                        msg.sender.get_position() - msg.receiver.get_position(),
+                    );
+                    match self.try_push_item(item, direction, msg.tick_id) {
+                        Some(item) => {
+                            msg.body = field_message::MessageBody::PushItem(item);
+                            Some(message)
+                        }
+                        None => None,
+                    }
+                }
+            },
+        }
+    }
+}
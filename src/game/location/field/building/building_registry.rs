@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use crate::game::{
+    common::{asset_manager::AssetManager, json_reader::JsonReader},
+    location::field::fluid::FluidFactory,
+};
+
+use super::{
+    belt_router::BeltRouter,
+    craft_station::CraftStation,
+    electric_pole::ElectricPole,
+    error_building::ErrorBuilding,
+    inserter::Inserter,
+    item::{Item, ItemFactory},
+    lab::Lab,
+    miner::Miner,
+    pipe::Pipe,
+    recycler::Recycler,
+    storage_chest::StorageChest,
+    transport_belt::TransportBelt,
+    underground_belt::UndergroundBelt,
+    Building,
+};
+
+// Prototypes of all the buildings declared in dictionaries, keyed by name.
+pub struct BuildingRegistry {
+    prototypes: HashMap<String, Box<dyn Building>>,
+    // Items paid on placement and refunded on deconstruction, from the optional "cost".
+    costs: HashMap<String, Vec<(Item, usize)>>,
+    // In order of declaration.
+    names: Vec<String>,
+}
+
+impl BuildingRegistry {
+    pub fn new(
+        asset_manager: &AssetManager,
+        item_factory: &ItemFactory,
+        fluid_factory: &FluidFactory,
+    ) -> BuildingRegistry {
+        let mut registry = BuildingRegistry {
+            prototypes: HashMap::new(),
+            costs: HashMap::new(),
+            names: vec![],
+        };
+
+        for obj in Self::read_dictionary(asset_manager, "dictionaries/recyclers.json", "recyclers")
+        {
+            let mut recycler = Recycler::from_json_object(&obj);
+            recycler.init_items(item_factory);
+            registry.register(Box::from(recycler), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(asset_manager, "dictionaries/miners.json", "miners") {
+            let miner = Miner::from_json_object(&obj, item_factory);
+            registry.register(Box::from(miner), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(
+            asset_manager,
+            "dictionaries/transport_belts.json",
+            "transport_belts",
+        ) {
+            // Plain belts have no "type".
+            let belt: Box<dyn Building> = match obj.get("type").and_then(|kind| kind.as_str()) {
+                Some("underground entrance") | Some("underground exit") => {
+                    Box::from(UndergroundBelt::from_json_object(&obj))
+                }
+                Some(_) => Box::from(BeltRouter::from_json_object(&obj, item_factory)),
+                None => Box::from(TransportBelt::from_json_object(&obj)),
+            };
+            registry.register(belt, &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(
+            asset_manager,
+            "dictionaries/craft_stations.json",
+            "craft_stations",
+        ) {
+            let station = CraftStation::from_json_object(&obj, item_factory);
+            registry.register(Box::from(station), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(
+            asset_manager,
+            "dictionaries/electric_poles.json",
+            "electric_poles",
+        ) {
+            let pole = ElectricPole::from_json_object(&obj);
+            registry.register(Box::from(pole), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(asset_manager, "dictionaries/inserters.json", "inserters")
+        {
+            let inserter = Inserter::from_json_object(&obj, item_factory);
+            registry.register(Box::from(inserter), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(asset_manager, "dictionaries/pipes.json", "pipes") {
+            let pipe = Pipe::from_json_object(&obj, fluid_factory);
+            registry.register(Box::from(pipe), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(
+            asset_manager,
+            "dictionaries/storage_chests.json",
+            "storage_chests",
+        ) {
+            let chest = StorageChest::from_json_object(&obj);
+            registry.register(Box::from(chest), &obj, item_factory);
+        }
+
+        for obj in Self::read_dictionary(asset_manager, "dictionaries/labs.json", "labs") {
+            let lab = Lab::from_json_object(&obj);
+            registry.register(Box::from(lab), &obj, item_factory);
+        }
+
+        log::info!("{} buildings are loaded", registry.names.len());
+        registry
+    }
+
+    fn read_dictionary(
+        asset_manager: &AssetManager,
+        path: &str,
+        array_name: &str,
+    ) -> Vec<serde_json::Value> {
+        let json = asset_manager.get_json(AssetManager::get_asset_id(path));
+        let dictionary = serde_json::from_str(json.as_ref()).unwrap_or_else(|e| {
+            log::error!(
+                "Building dictionary {} haven't been succesfully loaded : {}",
+                path,
+                e
+            );
+            serde_json::Value::Null
+        });
+
+        let mut error = false;
+        let objects = JsonReader::read_vec(&dictionary, array_name, &mut error);
+        if error {
+            log::error!(
+                "Building dictionary {} haven't been succesfully loaded : wrong JSON file structure",
+                path
+            );
+        }
+        objects
+    }
+
+    fn register(
+        &mut self,
+        prototype: Box<dyn Building>,
+        obj: &serde_json::Value,
+        item_factory: &ItemFactory,
+    ) {
+        let name = String::from(prototype.get_name());
+        if self.prototypes.contains_key(&name) {
+            log::error!("Building {} is declared more than once", name);
+            return;
+        }
+
+        let mut error = false;
+        let cost = match obj.get("cost") {
+            Some(_) => JsonReader::read_vec(obj, "cost", &mut error)
+                .iter()
+                .map(|item| {
+                    let item_name = JsonReader::read_string(item, "item", &mut error);
+                    let amount = JsonReader::read_u32(item, "amount", &mut error) as usize;
+                    (
+                        item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name)),
+                        amount,
+                    )
+                })
+                .collect(),
+            None => vec![],
+        };
+        if error {
+            log::error!("Failed to parse cost of building {}", name);
+        }
+
+        self.names.push(name.clone());
+        self.costs.insert(name.clone(), cost);
+        self.prototypes.insert(name, prototype);
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.prototypes.contains_key(name)
+    }
+
+    pub fn get_names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn get_cost(&self, name: &str) -> &[(Item, usize)] {
+        self.costs.get(name).map_or(&[], |cost| cost.as_slice())
+    }
+
+    pub fn create(&self, name: &str) -> Box<dyn Building> {
+        match self.prototypes.get(name) {
+            Some(prototype) => prototype.clone_box(),
+            None => {
+                log::error!("There's no such building {}", name);
+                Box::from(ErrorBuilding::new())
+            }
+        }
+    }
+}
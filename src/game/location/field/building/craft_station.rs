@@ -1,27 +1,61 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde_json::json;
+
 use crate::game::{
     common::{
         asset_manager::{AssetId, AssetManager},
+        direction::Direction,
         json_reader::JsonReader,
+        math::IVec2,
     },
+    location::event::BuildingEvent,
     renderer::Sprite,
 };
 
 use super::{
-    item::{Item, ItemFactory},
+    item::{Item, ItemFactory, ItemId, TransportedItem},
+    item_sides::ItemSides,
     *,
 };
 
 #[derive(Clone)]
 struct CraftRecipe {
+    // Name of the first output by default, technologies unlock recipes by it.
+    name: String,
     inputs: Vec<(Item, usize)>,
     outputs: Vec<(Item, usize)>,
+    // In ticks.
+    duration: u32,
 }
 
 pub struct CraftStation {
     name: String,
     texture: AssetId,
+    size: IVec2,
+    facing: Direction,
+    item_sides: ItemSides,
 
     recipes: Vec<CraftRecipe>,
+    // Recipes of the technologies not researched yet, set by the field.
+    locked_recipes: Vec<bool>,
+
+    // Recipe that is crafted continuously when the queue is empty.
+    active_recipe: Option<usize>,
+    // Recipe ids with the amount of crafts left.
+    craft_queue: VecDeque<(usize, u32)>,
+    curr_craft: Option<usize>,
+    craft_progress: u32,
+
+    // Each recipe collects its own inputs.
+    input_bufs: Vec<HashMap<ItemId, u32>>,
+    output_buf: HashMap<ItemId, u32>,
+
+    // Amount of crafts to queue from gui.
+    gui_queue_amount: i32,
+
+    // Taken by the field at the end of the tick.
+    events: Vec<BuildingEvent>,
 }
 
 impl CraftStation {
@@ -29,6 +63,8 @@ impl CraftStation {
         self.name = JsonReader::read_string(obj, "name", error);
         let tex_path = JsonReader::read_string(obj, "texture", error);
         self.texture = AssetManager::get_asset_id(&tex_path);
+        self.size = size_from_json_object(obj, error);
+        self.item_sides = ItemSides::from_json_object(obj, error);
     }
 
     fn recipes_from_json_object(
@@ -41,6 +77,13 @@ impl CraftStation {
         self.recipes = recipes
             .into_iter()
             .map(|recipe_json| CraftRecipe {
+                name: match recipe_json.get("name") {
+                    Some(_) => JsonReader::read_string(&recipe_json, "name", error),
+                    None => JsonReader::read_vec(&recipe_json, "outputs", error)
+                        .first()
+                        .map(|output_json| JsonReader::read_string(output_json, "item", error))
+                        .unwrap_or_default(),
+                },
                 inputs: JsonReader::read_vec(&recipe_json, "inputs", error)
                     .into_iter()
                     .map(|input_json| {
@@ -63,6 +106,20 @@ impl CraftStation {
                         )
                     })
                     .collect(),
+                duration: JsonReader::read_u32(&recipe_json, "duration", error),
+            })
+            .collect();
+        self.locked_recipes = vec![false; self.recipes.len()];
+
+        self.input_bufs = self
+            .recipes
+            .iter()
+            .map(|recipe| {
+                recipe
+                    .inputs
+                    .iter()
+                    .map(|(item, _)| (item.get_id(), 0))
+                    .collect()
             })
             .collect();
     }
@@ -71,21 +128,259 @@ impl CraftStation {
         let mut station = CraftStation {
             name: String::new(),
             texture: AssetId::null(),
+            size: IVec2::new(1, 1),
+            facing: Direction::Up,
+            item_sides: ItemSides::default(),
             recipes: vec![],
+            locked_recipes: vec![],
+
+            active_recipe: None,
+            craft_queue: VecDeque::new(),
+            curr_craft: None,
+            craft_progress: 0,
+
+            input_bufs: vec![],
+            output_buf: HashMap::new(),
+
+            gui_queue_amount: 1,
+
+            events: vec![],
         };
 
         let mut error = false;
         station.common_data_from_json_object(obj, &mut error);
         station.recipes_from_json_object(item_factory, obj, &mut error);
 
+        if error {
+            log::error!(
+                "Failed to parse CraftStation from json ({})",
+                if station.name.is_empty() {
+                    "error loading name"
+                } else {
+                    &station.name
+                }
+            );
+        } else {
+            log::info!("CraftStation succesfully loaded({})", station.name);
+        }
+
         station
     }
+
+    pub fn select_recipe(&mut self, recipe_id: Option<usize>) {
+        self.active_recipe =
+            recipe_id.filter(|&id| id < self.recipes.len() && !self.locked_recipes[id]);
+    }
+
+    pub fn queue_crafts(&mut self, recipe_id: usize, amount: u32) {
+        if recipe_id >= self.recipes.len() || self.locked_recipes[recipe_id] || amount == 0 {
+            return;
+        }
+
+        match self.craft_queue.back_mut() {
+            Some((last_recipe, last_amount)) if *last_recipe == recipe_id => *last_amount += amount,
+            _ => self.craft_queue.push_back((recipe_id, amount)),
+        }
+    }
+
+    // Queued crafts go first, then the active recipe.
+    fn get_next_recipe(&self) -> Option<usize> {
+        match self.craft_queue.front() {
+            Some(&(recipe_id, _)) => Some(recipe_id),
+            None => self.active_recipe,
+        }
+    }
+
+    fn has_inputs(&self, recipe_id: usize) -> bool {
+        self.recipes[recipe_id]
+            .inputs
+            .iter()
+            .all(|(item, amount)| self.input_bufs[recipe_id][&item.get_id()] as usize >= *amount)
+    }
+
+    fn start_craft(&mut self, recipe_id: usize) {
+        for (item, amount) in &self.recipes[recipe_id].inputs {
+            *self.input_bufs[recipe_id].get_mut(&item.get_id()).unwrap() -= *amount as u32;
+        }
+
+        if let Some((_, amount)) = self.craft_queue.front_mut() {
+            *amount -= 1;
+            if *amount == 0 {
+                self.craft_queue.pop_front();
+            }
+        }
+
+        self.curr_craft = Some(recipe_id);
+        self.craft_progress = 0;
+    }
+
+    fn finish_craft(&mut self, recipe_id: usize) {
+        let recipe = &self.recipes[recipe_id];
+        for (item, amount) in &recipe.outputs {
+            *self.output_buf.entry(item.get_id()).or_insert(0) += *amount as u32;
+            self.events.push(BuildingEvent::ItemProduced {
+                item: item.clone(),
+                amount: *amount,
+            });
+        }
+        self.events.push(BuildingEvent::RecipeCompleted {
+            recipe: recipe.name.clone(),
+        });
+
+        self.curr_craft = None;
+        self.craft_progress = 0;
+    }
+
+    // Recipes that need the item, the ones that will be crafted sooner go first.
+    fn get_accepting_recipes(&self, item_id: ItemId) -> Vec<usize> {
+        let mut recipe_ids: Vec<usize> = self
+            .craft_queue
+            .iter()
+            .map(|&(recipe_id, _)| recipe_id)
+            .chain(self.active_recipe)
+            .collect();
+        recipe_ids.dedup();
+
+        recipe_ids
+            .into_iter()
+            .filter(|&recipe_id| {
+                !self.locked_recipes[recipe_id]
+                    && self.recipes[recipe_id].inputs.iter().any(|(item, amount)| {
+                        item.get_id() == item_id
+                            && (self.input_bufs[recipe_id][&item_id] as usize) < *amount
+                    })
+            })
+            .collect()
+    }
+
+    fn find_item_prototype(&self, item_id: ItemId) -> Option<&Item> {
+        self.recipes
+            .iter()
+            .flat_map(|recipe| recipe.inputs.iter().chain(recipe.outputs.iter()))
+            .map(|(item, _)| item)
+            .find(|item| item.get_id() == item_id)
+    }
+
+    fn save_item_buffer(&self, buffer: &HashMap<ItemId, u32>) -> serde_json::Value {
+        let mut items: Vec<(&str, u32)> = buffer
+            .iter()
+            .filter(|(_, &amount)| amount > 0)
+            .filter_map(|(&id, &amount)| {
+                self.find_item_prototype(id)
+                    .map(|item| (item.get_name(), amount))
+            })
+            .collect();
+        items.sort();
+
+        serde_json::Value::Array(
+            items
+                .into_iter()
+                .map(|(item, amount)| json!({ "item": item, "amount": amount }))
+                .collect(),
+        )
+    }
+
+    fn load_item_buffer(
+        &self,
+        buffer: &mut HashMap<ItemId, u32>,
+        saved_buffer: &serde_json::Value,
+        error: &mut bool,
+    ) {
+        for item in saved_buffer.as_array().cloned().unwrap_or_else(|| {
+            *error = true;
+            vec![]
+        }) {
+            let id =
+                ItemFactory::get_item_id_by_name(&JsonReader::read_string(&item, "item", error));
+            let amount = JsonReader::read_u32(&item, "amount", error);
+            if self.find_item_prototype(id).is_none() {
+                *error = true;
+                continue;
+            }
+            buffer.insert(id, amount);
+        }
+    }
+
+    fn pull_item_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        // Sorted by name so that messages order doesn't depend on HashMap.
+        let mut item_ids: Vec<(&str, ItemId)> = self
+            .output_buf
+            .keys()
+            .filter_map(|&id| {
+                self.find_item_prototype(id)
+                    .map(|item| (item.get_name(), id))
+            })
+            .collect();
+        item_ids.sort_by(|a, b| a.0.cmp(b.0));
+        let item_ids: Vec<ItemId> = item_ids.into_iter().map(|(_, id)| id).collect();
+
+        let mut messages = Vec::new();
+        for item_id in item_ids {
+            let item_count = self.output_buf.insert(item_id, 0).unwrap();
+            let item_prototype = self.find_item_prototype(item_id).unwrap();
+
+            for _ in 0..item_count {
+                messages.push(Message::FieldMessage(field_message::Message {
+                    id: messages.len() as u32,
+                    sender: field_message::MessageExchangeActor::default(),
+                    receiver: field_message::MessageExchangeActor::default(),
+                    target: field_message::Target::Directions(
+                        self.item_sides.get_output_directions(self.facing),
+                    ),
+                    tick_id,
+                    refund: false,
+                    ground_cell: None,
+                    body: field_message::MessageBody::PushItem(TransportedItem::new(
+                        item_prototype.clone(),
+                    )),
+                }));
+            }
+        }
+
+        messages
+    }
+
+    fn render_items_gui(params: &mut GuiRenderParams, items: &[(Item, usize)]) {
+        let mut first = true;
+        for (item, amount) in items {
+            if !first {
+                params.ui.text(" + ");
+                params.ui.same_line();
+            } else {
+                first = false;
+            }
+
+            imgui::Image::new(
+                params.get_or_load_texture_id(item.get_sprite_asset_id()),
+                [20.0, 20.0],
+            )
+            .build(params.ui);
+            params.ui.same_line();
+            params.ui.text(&format!(" x{} ", amount));
+            params.ui.same_line();
+        }
+    }
 }
 
 impl GameEntity for CraftStation {
     fn update(&mut self, _parameters: &UpdateParameters) {}
 
-    fn tick(&mut self, _tick_id: u32) {}
+    fn tick(&mut self, _tick_id: u32) {
+        if let Some(recipe_id) = self.curr_craft {
+            self.craft_progress += 1;
+            if self.craft_progress >= self.recipes[recipe_id].duration {
+                self.finish_craft(recipe_id);
+            }
+        }
+
+        if self.curr_craft.is_none() {
+            if let Some(recipe_id) = self.get_next_recipe() {
+                if !self.locked_recipes[recipe_id] && self.has_inputs(recipe_id) {
+                    self.start_craft(recipe_id);
+                }
+            }
+        }
+    }
 
     fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
         let sprite = Sprite::new(self.texture);
@@ -95,84 +390,330 @@ impl GameEntity for CraftStation {
 
 impl WithGui for CraftStation {
     fn render_gui(&mut self, params: &mut GuiRenderParams) {
-        imgui::Window::new("craft")
-            .position([0.0, 0.0], imgui::Condition::Always)
-            .build(&params.ui, || {
-                params.ui.text("recipes:");
-                for recipe in &self.recipes {
-                    params.ui.separator();
-
-                    params.ui.button("craft");
-                    params.ui.same_line();
-
-                    let mut first = true;
-                    for (item, amount) in &recipe.inputs {
-                        if !first {
-                            params.ui.text(" + ");
-                            params.ui.same_line();
-                        } else {
-                            first = false;
-                        }
-
-                        imgui::Image::new(
-                            params.get_or_load_texture_id(item.get_sprite_asset_id()),
-                            [20.0, 20.0],
-                        )
-                        .build(params.ui);
-                        params.ui.same_line();
-                        params.ui.text(&format!(" x{} ", amount));
-                        params.ui.same_line();
-                    }
+        match self.curr_craft {
+            Some(recipe_id) => {
+                let duration = self.recipes[recipe_id].duration.max(1);
+                imgui::ProgressBar::new(self.craft_progress as f32 / duration as f32)
+                    .size([200.0, 0.0])
+                    .build(params.ui);
+            }
+            None => params.ui.text("idle"),
+        }
 
-                    params.ui.text(" -> ");
+        params
+            .ui
+            .input_int("amount", &mut self.gui_queue_amount)
+            .build();
+        self.gui_queue_amount = self.gui_queue_amount.max(1);
 
-                    let mut first = true;
-                    for (item, amount) in &recipe.outputs {
-                        params.ui.same_line();
-                        if !first {
-                            params.ui.text(" + ");
-                            params.ui.same_line();
-                        } else {
-                            first = false;
-                        }
+        params.ui.text("recipes:");
+        for recipe_id in 0..self.recipes.len() {
+            params.ui.separator();
+            let id_token = params.ui.push_id(recipe_id as i32);
 
-                        imgui::Image::new(
-                            params.get_or_load_texture_id(item.get_sprite_asset_id()),
-                            [20.0, 20.0],
-                        )
-                        .build(params.ui);
-                        params.ui.same_line();
-                        params.ui.text(&format!(" x{} ", amount));
-                    }
+            if self.locked_recipes[recipe_id] {
+                params.ui.text_disabled("locked");
+            } else {
+                let selected = self.active_recipe == Some(recipe_id);
+                if params.ui.button(if selected { "stop" } else { "craft" }) {
+                    self.select_recipe(if selected { None } else { Some(recipe_id) });
                 }
-            });
+                params.ui.same_line();
+                if params.ui.button("queue") {
+                    self.queue_crafts(recipe_id, self.gui_queue_amount as u32);
+                }
+            }
+            params.ui.same_line();
+            params.ui.text(&self.recipes[recipe_id].name);
+            params.ui.same_line();
+
+            let recipe = &self.recipes[recipe_id];
+            Self::render_items_gui(params, &recipe.inputs);
+            params.ui.text(" -> ");
+            params.ui.same_line();
+            Self::render_items_gui(params, &recipe.outputs);
+            params.ui.new_line();
+
+            let queued: u32 = self
+                .craft_queue
+                .iter()
+                .filter(|(id, _)| *id == recipe_id)
+                .map(|(_, amount)| amount)
+                .sum();
+            if queued > 0 {
+                params.ui.text(&format!("queued: {}", queued));
+            }
+
+            let collected: Vec<String> = recipe
+                .inputs
+                .iter()
+                .map(|(item, amount)| {
+                    format!(
+                        "{} {}/{}",
+                        item.get_name(),
+                        self.input_bufs[recipe_id][&item.get_id()],
+                        amount
+                    )
+                })
+                .collect();
+            if !collected.is_empty() {
+                params
+                    .ui
+                    .text(format!("collected: {}", collected.join(", ")));
+            }
+
+            id_token.pop();
+        }
+
+        let outputs = self.get_sorted_output_items();
+        if !outputs.is_empty() {
+            params.ui.separator();
+            for (item, amount) in outputs {
+                params
+                    .ui
+                    .text(format!("output {}: {}", item.get_name(), amount));
+            }
+        }
     }
 }
 
 impl BuildingClone for CraftStation {
     fn clone_box(&self) -> Box<dyn Building> {
+        let mut input_bufs = self.input_bufs.clone();
+        for buf in &mut input_bufs {
+            for val in buf.values_mut() {
+                *val = 0;
+            }
+        }
+
         Box::from(CraftStation {
             name: self.name.clone(),
             texture: self.texture.clone(),
+            size: self.size,
+            facing: self.facing,
+            item_sides: self.item_sides.clone(),
             recipes: self.recipes.clone(),
+            locked_recipes: self.locked_recipes.clone(),
+
+            active_recipe: None,
+            craft_queue: VecDeque::new(),
+            curr_craft: None,
+            craft_progress: 0,
+
+            input_bufs,
+            output_buf: HashMap::new(),
+
+            gui_queue_amount: 1,
+
+            events: vec![],
         })
     }
 }
 
 impl Building for CraftStation {
     fn get_name(&self) -> &str {
-        "error"
+        &self.name
+    }
+
+    fn get_size(&self) -> IVec2 {
+        rotate_size(self.size, self.facing)
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    // Inputs of the craft in progress are counted too.
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        let mut contents = self.get_sorted_output_items();
+        for (recipe_id, buf) in self.input_bufs.iter().enumerate() {
+            let crafting = self.curr_craft == Some(recipe_id);
+            for (item, amount) in &self.recipes[recipe_id].inputs {
+                let held = buf[&item.get_id()] as usize + if crafting { *amount } else { 0 };
+                if held > 0 {
+                    contents.push((item.clone(), held));
+                }
+            }
+        }
+        contents
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    fn get_output_items(&self) -> Vec<(Item, usize)> {
+        self.get_sorted_output_items()
+    }
+
+    fn take_output_items(&mut self, item_id: ItemId, amount: usize) -> usize {
+        match self.output_buf.get_mut(&item_id) {
+            Some(buffered) => {
+                let taken = amount.min(*buffered as usize);
+                *buffered -= taken as u32;
+                taken
+            }
+            None => 0,
+        }
+    }
+
+    // Items go to the queued and active recipes, as the pushed ones do.
+    fn put_input_items(&mut self, item: &Item, amount: usize) -> usize {
+        let item_id = item.get_id();
+        let mut accepted = 0;
+        while accepted < amount {
+            match self.get_accepting_recipes(item_id).first() {
+                Some(&recipe_id) => {
+                    *self.input_bufs[recipe_id].get_mut(&item_id).unwrap() += 1;
+                    accepted += 1;
+                }
+                None => break,
+            }
+        }
+        accepted
+    }
+
+    fn take_events(&mut self) -> Vec<BuildingEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn as_recipe_lock_mut(&mut self) -> Option<&mut dyn RecipeLockBuilding> {
+        Some(self)
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        json!({
+            "active_recipe": self.active_recipe,
+            "craft_queue": self
+                .craft_queue
+                .iter()
+                .map(|&(recipe, amount)| json!({ "recipe": recipe, "amount": amount }))
+                .collect::<Vec<_>>(),
+            "curr_craft": self.curr_craft,
+            "craft_progress": self.craft_progress,
+            "input_bufs": self
+                .input_bufs
+                .iter()
+                .map(|buf| self.save_item_buffer(buf))
+                .collect::<Vec<_>>(),
+            "output_buf": self.save_item_buffer(&self.output_buf),
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        _item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        let read_recipe_id = |name: &str, error: &mut bool| match state.get(name) {
+            Some(serde_json::Value::Null) => None,
+            Some(id) => match id.as_u64() {
+                Some(id) if (id as usize) < self.recipes.len() => Some(id as usize),
+                _ => {
+                    *error = true;
+                    None
+                }
+            },
+            None => {
+                *error = true;
+                None
+            }
+        };
+        self.active_recipe = read_recipe_id("active_recipe", error);
+        self.curr_craft = read_recipe_id("curr_craft", error);
+        self.craft_progress = JsonReader::read_u32(state, "craft_progress", error);
+
+        self.craft_queue = VecDeque::new();
+        for queued in JsonReader::read_vec(state, "craft_queue", error) {
+            let recipe_id = JsonReader::read_u32(&queued, "recipe", error) as usize;
+            let amount = JsonReader::read_u32(&queued, "amount", error);
+            if recipe_id < self.recipes.len() {
+                self.craft_queue.push_back((recipe_id, amount));
+            } else {
+                *error = true;
+            }
+        }
+
+        let saved_input_bufs = JsonReader::read_vec(state, "input_bufs", error);
+        if saved_input_bufs.len() != self.input_bufs.len() {
+            *error = true;
+            return;
+        }
+        let mut input_bufs = self.input_bufs.clone();
+        for (buf, saved_buf) in input_bufs.iter_mut().zip(saved_input_bufs.iter()) {
+            self.load_item_buffer(buf, saved_buf, error);
+        }
+        self.input_bufs = input_bufs;
+
+        let mut output_buf = HashMap::new();
+        let saved_output_buf = JsonReader::read_vec(state, "output_buf", error);
+        self.load_item_buffer(
+            &mut output_buf,
+            &serde_json::Value::Array(saved_output_buf),
+            error,
+        );
+        self.output_buf = output_buf;
+    }
+}
+
+impl CraftStation {
+    fn get_sorted_output_items(&self) -> Vec<(Item, usize)> {
+        let mut items: Vec<(Item, usize)> = self
+            .output_buf
+            .iter()
+            .filter(|(_, &amount)| amount > 0)
+            .filter_map(|(&id, &amount)| {
+                self.find_item_prototype(id)
+                    .map(|item| (item.clone(), amount as usize))
+            })
+            .collect();
+        items.sort_by(|a, b| a.0.get_name().cmp(b.0.get_name()));
+        items
+    }
+}
+
+impl RecipeLockBuilding for CraftStation {
+    fn set_locked_recipes(&mut self, locked: &[String]) {
+        for (recipe, is_locked) in self.recipes.iter().zip(self.locked_recipes.iter_mut()) {
+            *is_locked = locked.contains(&recipe.name);
+        }
     }
 }
 
 impl MessageSender for CraftStation {
-    fn pull_messages(&mut self, _tick_id: u32) -> Vec<Message> {
-        Vec::new()
+    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        self.pull_item_messages(tick_id)
     }
 }
 
 impl MessageReceiver for CraftStation {
     fn try_push_message(&mut self, message: Message) -> Option<Message> {
-        Some(message)
+        match &message {
+            Message::FieldMessage(msg) => match &msg.body {
+                field_message::MessageBody::PushItem(item) => {
+                    let item_id = item.get_id();
+                    if msg.refund {
+                        *self.output_buf.entry(item_id).or_insert(0) += 1;
+                        return None;
+                    }
+
+                    let direction = Direction::from_ivec2(
+                        msg.sender.get_position() - msg.receiver.get_position(),
+                    );
+                    if !self.item_sides.accepts_from(direction, self.facing) {
+                        return Some(message);
+                    }
+
+                    match self.get_accepting_recipes(item_id).first() {
+                        Some(&recipe_id) => {
+                            *self.input_bufs[recipe_id].get_mut(&item_id).unwrap() += 1;
+                            None
+                        }
+                        None => Some(message),
+                    }
+                }
+            },
+        }
     }
 }
@@ -0,0 +1,132 @@
+use crate::game::{
+    common::{
+        asset_manager::{AssetId, AssetManager},
+        direction::Direction,
+        json_reader::JsonReader,
+    },
+    location::field::power::{ElectricPort, ElectricPortMode},
+    renderer::Sprite,
+};
+
+use super::{item::ItemFactory, *};
+
+// Connects the ports of the same voltage within its range.
+pub struct ElectricPole {
+    name: String,
+    texture: AssetId,
+    facing: Direction,
+
+    range: u32,
+    ports: Vec<ElectricPort>,
+}
+
+impl ElectricPole {
+    pub fn from_json_object(obj: &serde_json::Value) -> ElectricPole {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let tex_path = JsonReader::read_string(obj, "texture", &mut error);
+        let voltage = JsonReader::read_u32(obj, "voltage", &mut error);
+        let range = JsonReader::read_u32(obj, "range", &mut error);
+
+        if error {
+            log::error!(
+                "Failed to parse ElectricPole from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("ElectricPole succesfully loaded({})", name);
+        }
+
+        ElectricPole {
+            name,
+            texture: AssetManager::get_asset_id(&tex_path),
+            facing: Direction::Up,
+            range,
+            ports: vec![ElectricPort {
+                mode: ElectricPortMode::Transit,
+                voltage,
+                energy: 0,
+            }],
+        }
+    }
+}
+
+impl GameEntity for ElectricPole {
+    fn update(&mut self, _parameters: &UpdateParameters) {}
+
+    fn tick(&mut self, _tick_id: u32) {}
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let sprite = Sprite::new(self.texture);
+        renderer.queue_render_sprite(sprite, transform);
+    }
+}
+
+impl WithGui for ElectricPole {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        params.ui.text(format!("range: {}", self.range));
+    }
+}
+
+impl BuildingClone for ElectricPole {
+    fn clone_box(&self) -> Box<dyn Building> {
+        Box::from(ElectricPole {
+            name: self.name.clone(),
+            texture: self.texture,
+            facing: self.facing,
+            range: self.range,
+            ports: self.ports.clone(),
+        })
+    }
+}
+
+impl Building for ElectricPole {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::Value::Object(serde_json::Map::new())
+    }
+
+    fn load_state(
+        &mut self,
+        _state: &serde_json::Value,
+        _item_factory: &ItemFactory,
+        _error: &mut bool,
+    ) {
+    }
+
+    fn get_electric_ports(&self) -> &[ElectricPort] {
+        &self.ports
+    }
+
+    fn get_electric_range(&self) -> u32 {
+        self.range
+    }
+}
+
+impl MessageSender for ElectricPole {
+    fn pull_messages(&mut self, _tick_id: u32) -> Vec<Message> {
+        Vec::new()
+    }
+}
+
+impl MessageReceiver for ElectricPole {
+    fn try_push_message(&mut self, message: Message) -> Option<Message> {
+        Some(message)
+    }
+}
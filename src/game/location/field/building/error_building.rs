@@ -1,6 +1,8 @@
 use super::*;
 
-struct ErrorBuilding {}
+pub struct ErrorBuilding {
+    facing: Direction,
+}
 
 impl GameEntity for ErrorBuilding {
     fn update(&mut self, _parameters: &UpdateParameters) {}
@@ -22,11 +24,33 @@ impl Building for ErrorBuilding {
     fn get_name(&self) -> &str {
         "error"
     }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::Value::Object(serde_json::Map::new())
+    }
+
+    fn load_state(
+        &mut self,
+        _state: &serde_json::Value,
+        _item_factory: &ItemFactory,
+        _error: &mut bool,
+    ) {
+    }
 }
 
 impl ErrorBuilding {
     pub fn new() -> ErrorBuilding {
-        ErrorBuilding {}
+        ErrorBuilding {
+            facing: Direction::Up,
+        }
     }
 }
 
@@ -0,0 +1,427 @@
+use serde_json::json;
+
+use crate::game::{
+    common::{
+        asset_manager::{AssetId, AssetManager},
+        direction::Direction,
+        json_reader::JsonReader,
+        math::Vec2,
+    },
+    renderer::Sprite,
+};
+
+use super::{
+    item::{stack_transported_items, Item, ItemFactory, TransportedItem},
+    *,
+};
+
+const BASE_SCALE: f32 = 0.5;
+const HAND_SCALE: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq)]
+enum ArmState {
+    // Empty hand behind, the field gives it the item from the building there.
+    WaitingToPickUp,
+    // Progress is in ticks, the swing takes the swing period each way.
+    Carrying { progress: u32 },
+    // Holds the item in front until it's accepted.
+    WaitingToDrop,
+    Returning { progress: u32 },
+}
+
+impl ArmState {
+    fn get_name(&self) -> &'static str {
+        match self {
+            ArmState::WaitingToPickUp => "waiting to pick up",
+            ArmState::Carrying { .. } => "carrying",
+            ArmState::WaitingToDrop => "waiting to drop",
+            ArmState::Returning { .. } => "returning",
+        }
+    }
+
+    fn from_name(name: &str, progress: u32) -> Option<ArmState> {
+        match name {
+            "waiting to pick up" => Some(ArmState::WaitingToPickUp),
+            "carrying" => Some(ArmState::Carrying { progress }),
+            "waiting to drop" => Some(ArmState::WaitingToDrop),
+            "returning" => Some(ArmState::Returning { progress }),
+            _ => None,
+        }
+    }
+
+    fn get_progress(&self) -> u32 {
+        match self {
+            ArmState::Carrying { progress } | ArmState::Returning { progress } => *progress,
+            _ => 0,
+        }
+    }
+}
+
+// Hand offset along the facing, from -0.5 behind to 0.5 in front.
+#[derive(Clone, Copy)]
+struct ArmMovement {
+    from: f32,
+    to: f32,
+    tick_id: u32,
+}
+
+// Arm taking one item per swing from the building behind and putting it into the one in front.
+pub struct Inserter {
+    name: String,
+    texture: AssetId,
+    facing: Direction,
+
+    swing_period: u32,
+    state: ArmState,
+    hand: Option<TransportedItem>,
+    movement: Option<ArmMovement>,
+    hand_position: Vec2,
+
+    // Only the chosen item is picked up, any if None.
+    filter: Option<Item>,
+    // Sorted by name.
+    choices: Vec<Item>,
+}
+
+impl Inserter {
+    pub fn from_json_object(obj: &serde_json::Value, item_factory: &ItemFactory) -> Inserter {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let tex_path = JsonReader::read_string(obj, "texture", &mut error);
+        let swing_period = Self::read_swing_period(obj, &mut error);
+
+        if error {
+            log::error!(
+                "Failed to parse Inserter from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("Inserter succesfully loaded({})", name);
+        }
+
+        Inserter {
+            name,
+            texture: AssetManager::get_asset_id(&tex_path),
+            facing: Direction::Up,
+            swing_period,
+            state: ArmState::WaitingToPickUp,
+            hand: None,
+            movement: None,
+            hand_position: Vec2::zero(),
+            filter: None,
+            choices: item_factory.get_sorted_items(),
+        }
+    }
+
+    fn read_swing_period(obj: &serde_json::Value, error: &mut bool) -> u32 {
+        let swing_period = JsonReader::read_u32(obj, "swing_period", error);
+        if swing_period == 0 {
+            log::error!("Inserter swing period must be positive");
+            *error = true;
+            return 1;
+        }
+        swing_period
+    }
+
+    fn get_arm_offset(&self) -> f32 {
+        let progress = self.state.get_progress() as f32 / self.swing_period as f32;
+        match self.state {
+            ArmState::WaitingToPickUp => -0.5,
+            ArmState::Carrying { .. } => progress - 0.5,
+            ArmState::WaitingToDrop => 0.5,
+            ArmState::Returning { .. } => 0.5 - progress,
+        }
+    }
+
+    fn to_hand_position(&self, offset: f32) -> Vec2 {
+        self.facing.to_ivec2().to_vec2() * offset
+    }
+
+    fn advance(&mut self) {
+        self.state = match self.state {
+            ArmState::Carrying { progress } if progress + 1 >= self.swing_period => {
+                ArmState::WaitingToDrop
+            }
+            ArmState::Carrying { progress } => ArmState::Carrying {
+                progress: progress + 1,
+            },
+            ArmState::Returning { progress } if progress + 1 >= self.swing_period => {
+                ArmState::WaitingToPickUp
+            }
+            ArmState::Returning { progress } => ArmState::Returning {
+                progress: progress + 1,
+            },
+            state => state,
+        };
+    }
+
+    // Hand and the item in it are moved with the same interpolation as the items on the belts.
+    fn set_movement(&mut self, from: f32, to: f32, tick_id: u32) {
+        self.movement = Some(ArmMovement { from, to, tick_id });
+        let (from, to) = (self.to_hand_position(from), self.to_hand_position(to));
+        if let Some(item) = self.hand.as_mut() {
+            item.set_movement(from, to, tick_id);
+        }
+    }
+}
+
+impl GameEntity for Inserter {
+    fn update(&mut self, parameters: &UpdateParameters) {
+        let offset = match self.movement {
+            Some(movement) if movement.tick_id + 1 == parameters.last_tick_id => {
+                let interpolation = parameters.from_last_tick / parameters.tick_period;
+                movement.from + (movement.to - movement.from) * interpolation
+            }
+            Some(movement) => movement.to,
+            None => self.get_arm_offset(),
+        };
+        self.hand_position = self.to_hand_position(offset);
+
+        if let Some(item) = self.hand.as_mut() {
+            item.update(parameters);
+        }
+    }
+
+    fn tick(&mut self, tick_id: u32) {
+        let from = self.get_arm_offset();
+        self.advance();
+        let to = self.get_arm_offset();
+        self.set_movement(from, to, tick_id);
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let base = Sprite::new(self.texture);
+        renderer.queue_render_sprite(base, transform.clone().add_scale(Vec2::new_xy(BASE_SCALE)));
+
+        let hand = Sprite::new(self.texture);
+        renderer.queue_render_sprite(
+            hand,
+            transform
+                .clone()
+                .add_translation(self.hand_position)
+                .add_scale(Vec2::new_xy(HAND_SCALE)),
+        );
+
+        if let Some(item) = self.hand.as_mut() {
+            item.render(renderer, transform);
+        }
+    }
+}
+
+impl WithGui for Inserter {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        params
+            .ui
+            .text(format!("swing period: {}", self.swing_period));
+        params.ui.text(format!("arm: {}", self.state.get_name()));
+        params.ui.text(format!(
+            "hand: {}",
+            self.hand
+                .as_ref()
+                .map_or("-", |item| item.get_item().get_name())
+        ));
+
+        params.ui.separator();
+        params.ui.text("filter:");
+        for choice in self.choices.iter() {
+            let is_current =
+                self.filter.as_ref().map(|item| item.get_id()) == Some(choice.get_id());
+            if imgui::Selectable::new(choice.get_name())
+                .selected(is_current)
+                .build(params.ui)
+            {
+                self.filter = if is_current {
+                    None
+                } else {
+                    Some(choice.clone())
+                };
+            }
+        }
+    }
+}
+
+impl BuildingClone for Inserter {
+    fn clone_box(&self) -> Box<dyn Building> {
+        Box::from(Inserter {
+            name: self.name.clone(),
+            texture: self.texture,
+            facing: self.facing,
+            swing_period: self.swing_period,
+            state: ArmState::WaitingToPickUp,
+            hand: None,
+            movement: None,
+            hand_position: Vec2::zero(),
+            filter: self.filter.clone(),
+            choices: self.choices.clone(),
+        })
+    }
+}
+
+impl Building for Inserter {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        stack_transported_items(self.hand.iter())
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+        let offset = self.get_arm_offset();
+        self.movement = None;
+        if let Some(item) = self.hand.as_mut() {
+            let position = facing.to_ivec2().to_vec2() * offset;
+            item.set_movement(position, position, item.last_tick_moved);
+        }
+    }
+
+    // Optional "item" to pick up only and "swing_period" in ticks.
+    fn load_config(&mut self, config: &serde_json::Value, error: &mut bool) {
+        if config.get("item").is_some() {
+            let item_name = JsonReader::read_string(config, "item", error);
+            self.filter = self
+                .choices
+                .iter()
+                .find(|choice| choice.get_name() == item_name)
+                .cloned();
+            if self.filter.is_none() {
+                log::error!("There's no such item {} to filter", item_name);
+                *error = true;
+            }
+        }
+        if config.get("swing_period").is_some() {
+            self.swing_period = Self::read_swing_period(config, error);
+        }
+    }
+
+    fn as_inserter(&self) -> Option<&dyn InserterBuilding> {
+        Some(self)
+    }
+
+    fn as_inserter_mut(&mut self) -> Option<&mut dyn InserterBuilding> {
+        Some(self)
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        json!({
+            "swing_period": self.swing_period,
+            "state": self.state.get_name(),
+            "progress": self.state.get_progress(),
+            "hand": match &self.hand {
+                Some(item) => item.save_state(),
+                None => serde_json::Value::Null,
+            },
+            "filter_item": self.filter.as_ref().map(|item| item.get_name()),
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        self.swing_period = Self::read_swing_period(state, error);
+        let progress = JsonReader::read_u32(state, "progress", error);
+        self.state = ArmState::from_name(&JsonReader::read_string(state, "state", error), progress)
+            .unwrap_or_else(|| {
+                *error = true;
+                ArmState::WaitingToPickUp
+            });
+        self.hand = match state.get("hand") {
+            None => {
+                *error = true;
+                None
+            }
+            Some(serde_json::Value::Null) => None,
+            Some(saved_item) => Some(TransportedItem::from_saved_state(
+                saved_item,
+                item_factory,
+                error,
+            )),
+        };
+        self.filter = match state.get("filter_item") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(_) => {
+                let item_name = JsonReader::read_string(state, "filter_item", error);
+                Some(item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name)))
+            }
+        };
+    }
+}
+
+impl InserterBuilding for Inserter {
+    fn is_waiting_to_pick_up(&self) -> bool {
+        self.state == ArmState::WaitingToPickUp
+    }
+
+    fn accepts_pick_up(&self, item: &Item) -> bool {
+        let passes_filter = match &self.filter {
+            Some(filter) => filter.get_id() == item.get_id(),
+            None => true,
+        };
+        self.is_waiting_to_pick_up() && passes_filter
+    }
+
+    fn pick_up(&mut self, item: Item) {
+        self.hand = Some(TransportedItem::new(item));
+        self.state = ArmState::Carrying { progress: 0 };
+    }
+}
+
+impl MessageSender for Inserter {
+    // Item in front is pushed the same way the belts do, the arm returns once it's accepted.
+    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        if self.state != ArmState::WaitingToDrop {
+            return vec![];
+        }
+        let item = match self.hand.take() {
+            Some(item) => item,
+            None => {
+                self.state = ArmState::Returning { progress: 0 };
+                return vec![];
+            }
+        };
+        self.state = ArmState::Returning { progress: 0 };
+
+        vec![Message::FieldMessage(field_message::Message {
+            id: 0,
+            sender: field_message::MessageExchangeActor::default(),
+            receiver: field_message::MessageExchangeActor::default(),
+            target: field_message::Target::Directions(vec![self.facing]),
+            tick_id,
+            refund: false,
+            ground_cell: None,
+            body: field_message::MessageBody::PushItem(item),
+        })]
+    }
+}
+
+impl MessageReceiver for Inserter {
+    // Takes back the refunded item only, pushing into the inserter is done by the field.
+    fn try_push_message(&mut self, message: Message) -> Option<Message> {
+        match &message {
+            Message::FieldMessage(msg) => match &msg.body {
+                field_message::MessageBody::PushItem(item) if msg.refund => {
+                    let mut item = item.clone();
+                    let position = self.to_hand_position(0.5);
+                    item.set_movement(position, position, msg.tick_id);
+                    self.hand = Some(item);
+                    self.state = ArmState::WaitingToDrop;
+                    None
+                }
+                field_message::MessageBody::PushItem(_) => Some(message),
+            },
+        }
+    }
+}
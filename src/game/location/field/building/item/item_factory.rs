@@ -43,6 +43,13 @@ impl ItemFactory {
         }
     }
 
+    // Sorted by name so that the order doesn't depend on HashMap.
+    pub fn get_sorted_items(&self) -> Vec<Item> {
+        let mut items: Vec<Item> = self.items.values().cloned().collect();
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        items
+    }
+
     pub fn get_item_id_by_name(name: &str) -> ItemId {
         let mut hasher = DefaultHasher::new();
         name.hash(&mut hasher);
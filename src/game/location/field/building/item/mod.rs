@@ -18,6 +18,7 @@ pub struct ItemId(u64);
 #[derive(Clone)]
 pub struct Item {
     id: ItemId,
+    name: String,
     sprite: Sprite,
 }
 
@@ -28,6 +29,7 @@ impl Item {
         let sprite = Sprite::new(texture);
         Item {
             id: ItemId(0),
+            name: String::from("error"),
             sprite,
         }
     }
@@ -56,6 +58,7 @@ impl Item {
                 let sprite = Sprite::new(AssetManager::get_asset_id(tex_path.as_str()));
                 Item {
                     id: ItemFactory::get_item_id_by_name(name.as_str()),
+                    name: name.clone(),
                     sprite,
                 }
             }
@@ -72,6 +75,10 @@ impl Item {
         self.id
     }
 
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     pub fn get_sprite_asset_id(&self) -> AssetId {
         self.sprite.texture
     }
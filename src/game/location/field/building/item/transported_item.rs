@@ -1,4 +1,7 @@
+use serde_json::json;
+
 use super::*;
+use crate::game::common::{json_reader::JsonReader, json_writer::JsonWriter};
 
 #[derive(Clone)]
 struct ItemMovement {
@@ -28,9 +31,72 @@ impl TransportedItem {
         self.item.get_id()
     }
 
+    pub fn get_item(&self) -> &Item {
+        &self.item
+    }
+
     pub fn set_movement(&mut self, from: Vec2, to: Vec2, tick_id: u32) {
         self.movement = Some(ItemMovement { from, to, tick_id });
     }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        let movement = match &self.movement {
+            Some(movement) => json!({
+                "from": JsonWriter::write_vec2(movement.from),
+                "to": JsonWriter::write_vec2(movement.to),
+                "tick_id": movement.tick_id,
+            }),
+            None => serde_json::Value::Null,
+        };
+
+        json!({
+            "item": self.item.get_name(),
+            "last_tick_moved": self.last_tick_moved,
+            "movement": movement,
+        })
+    }
+
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) -> TransportedItem {
+        let item_name = JsonReader::read_string(state, "item", error);
+        let item = item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name));
+
+        let movement = match state.get("movement") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(movement) => Some(ItemMovement {
+                from: JsonReader::read_vec2(movement, "from", error),
+                to: JsonReader::read_vec2(movement, "to", error),
+                tick_id: JsonReader::read_u32(movement, "tick_id", error),
+            }),
+        };
+
+        TransportedItem {
+            item,
+            last_tick_moved: JsonReader::read_u32(state, "last_tick_moved", error),
+            movement,
+        }
+    }
+}
+
+// Merges loose items into stacks, sorted by name.
+pub fn stack_transported_items<'a>(
+    items: impl Iterator<Item = &'a TransportedItem>,
+) -> Vec<(Item, usize)> {
+    let mut stacks: Vec<(Item, usize)> = vec![];
+    for transported in items {
+        match stacks
+            .iter_mut()
+            .find(|(item, _)| item.get_id() == transported.get_id())
+        {
+            Some((_, amount)) => *amount += 1,
+            None => stacks.push((transported.item.clone(), 1)),
+        }
+    }
+    stacks.sort_by(|a, b| a.0.get_name().cmp(b.0.get_name()));
+    stacks
 }
 
 impl GameEntity for TransportedItem {
@@ -41,7 +107,7 @@ impl GameEntity for TransportedItem {
             }
             Some(movement) => {
                 if movement.tick_id + 1 == parameters.last_tick_id {
-                    let interpolation = parameters.from_last_tick / crate::game::TICK_PERIOD;
+                    let interpolation = parameters.from_last_tick / parameters.tick_period;
                     self.item.sprite.local_transform.translation =
                         movement.from + (movement.to - movement.from) * interpolation;
                 } else {
@@ -0,0 +1,70 @@
+use crate::game::common::{direction::Direction, json_reader::JsonReader};
+
+// Sides to take items from and push items to, relative to the Up facing.
+#[derive(Clone)]
+pub struct ItemSides {
+    inputs: Vec<Direction>,
+    // Pushing is tried from the last one.
+    outputs: Vec<Direction>,
+}
+
+impl Default for ItemSides {
+    fn default() -> Self {
+        let all = vec![
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ];
+        ItemSides {
+            inputs: all.clone(),
+            outputs: all,
+        }
+    }
+}
+
+impl ItemSides {
+    // Optional "input_sides" and "output_sides", all the sides by default.
+    pub fn from_json_object(obj: &serde_json::Value, error: &mut bool) -> ItemSides {
+        let mut sides = ItemSides::default();
+        if obj.get("input_sides").is_some() {
+            sides.inputs = Self::read_sides(obj, "input_sides", error);
+        }
+        if obj.get("output_sides").is_some() {
+            sides.outputs = Self::read_sides(obj, "output_sides", error);
+        }
+        sides
+    }
+
+    fn read_sides(obj: &serde_json::Value, name: &str, error: &mut bool) -> Vec<Direction> {
+        JsonReader::read_vec(obj, name, error)
+            .iter()
+            .filter_map(|side| {
+                let side = side.as_str().and_then(Direction::from_name);
+                if side.is_none() {
+                    *error = true;
+                }
+                side
+            })
+            .collect()
+    }
+
+    // Direction is the absolute one from the building to the sender.
+    pub fn accepts_from(&self, direction: Direction, facing: Direction) -> bool {
+        self.inputs.contains(&direction.unrotated_by(facing))
+    }
+
+    pub fn get_input_directions(&self, facing: Direction) -> Vec<Direction> {
+        self.inputs
+            .iter()
+            .map(|side| side.rotated_by(facing))
+            .collect()
+    }
+
+    pub fn get_output_directions(&self, facing: Direction) -> Vec<Direction> {
+        self.outputs
+            .iter()
+            .map(|side| side.rotated_by(facing))
+            .collect()
+    }
+}
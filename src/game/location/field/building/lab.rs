@@ -0,0 +1,312 @@
+use serde_json::json;
+
+use crate::game::{
+    common::{
+        asset_manager::{AssetId, AssetManager},
+        direction::Direction,
+        json_reader::JsonReader,
+    },
+    renderer::Sprite,
+};
+
+use super::{
+    item::{Item, ItemFactory, ItemId},
+    *,
+};
+
+// Max amount of each item waiting to be consumed.
+const BUFFER_CAPACITY: usize = 10;
+
+// Consumes the items paid for the current research, one per period.
+pub struct Lab {
+    name: String,
+    texture: AssetId,
+    facing: Direction,
+
+    // In ticks.
+    period: u32,
+    from_last_consumption: u32,
+
+    input_buf: Vec<(Item, usize)>,
+    // Items left to pay, set by the field every tick.
+    demand: Vec<(Item, usize)>,
+    // Taken by the field at the end of the tick.
+    consumed: Vec<(Item, usize)>,
+}
+
+impl Lab {
+    pub fn from_json_object(obj: &serde_json::Value) -> Lab {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let tex_path = JsonReader::read_string(obj, "texture", &mut error);
+        let period = JsonReader::read_u32(obj, "period", &mut error);
+
+        if error {
+            log::error!(
+                "Failed to parse Lab from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("Lab succesfully loaded({})", name);
+        }
+
+        Lab {
+            name,
+            texture: AssetManager::get_asset_id(&tex_path),
+            facing: Direction::Up,
+            period,
+            from_last_consumption: 0,
+            input_buf: vec![],
+            demand: vec![],
+            consumed: vec![],
+        }
+    }
+
+    fn get_buffered(&self, item_id: ItemId) -> usize {
+        self.input_buf
+            .iter()
+            .find(|(item, _)| item.get_id() == item_id)
+            .map_or(0, |(_, amount)| *amount)
+    }
+
+    fn get_demanded(&self, item_id: ItemId) -> usize {
+        self.demand
+            .iter()
+            .find(|(item, _)| item.get_id() == item_id)
+            .map_or(0, |(_, amount)| *amount)
+    }
+
+    // Only the items the current research still needs are taken.
+    fn get_free_space(&self, item_id: ItemId) -> usize {
+        self.get_demanded(item_id)
+            .min(BUFFER_CAPACITY)
+            .saturating_sub(self.get_buffered(item_id))
+    }
+
+    fn put_items(&mut self, item: &Item, amount: usize) {
+        match self
+            .input_buf
+            .iter_mut()
+            .find(|(buffered, _)| buffered.get_id() == item.get_id())
+        {
+            Some((_, buffered)) => *buffered += amount,
+            None => self.input_buf.push((item.clone(), amount)),
+        }
+    }
+
+    fn take_items(&mut self, item_id: ItemId, amount: usize) -> usize {
+        let taken = match self
+            .input_buf
+            .iter_mut()
+            .find(|(item, _)| item.get_id() == item_id)
+        {
+            Some((_, buffered)) => {
+                let taken = amount.min(*buffered);
+                *buffered -= taken;
+                taken
+            }
+            None => 0,
+        };
+        self.input_buf.retain(|(_, amount)| *amount > 0);
+        taken
+    }
+
+    fn consume(&mut self) -> bool {
+        let item = match self
+            .input_buf
+            .iter()
+            .find(|(item, _)| self.get_demanded(item.get_id()) > 0)
+        {
+            Some((item, _)) => item.clone(),
+            None => return false,
+        };
+
+        self.take_items(item.get_id(), 1);
+        match self
+            .consumed
+            .iter_mut()
+            .find(|(consumed, _)| consumed.get_id() == item.get_id())
+        {
+            Some((_, amount)) => *amount += 1,
+            None => self.consumed.push((item, 1)),
+        }
+        true
+    }
+}
+
+impl GameEntity for Lab {
+    fn update(&mut self, _parameters: &UpdateParameters) {}
+
+    // Waits with the full progress until there's an item to consume.
+    fn tick(&mut self, _tick_id: u32) {
+        self.from_last_consumption = (self.from_last_consumption + 1).min(self.period);
+        if self.from_last_consumption >= self.period && self.consume() {
+            self.from_last_consumption = 0;
+        }
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let sprite = Sprite::new(self.texture);
+        renderer.queue_render_sprite(sprite, transform);
+    }
+}
+
+impl WithGui for Lab {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        if self.demand.is_empty() {
+            params.ui.text("no research");
+        } else {
+            imgui::ProgressBar::new(self.from_last_consumption as f32 / self.period.max(1) as f32)
+                .size([200.0, 0.0])
+                .build(params.ui);
+            params.ui.text("needed:");
+            for (item, amount) in &self.demand {
+                let label = format!("{}/{}", self.get_buffered(item.get_id()), amount);
+                render_item_gui(params, item, &label);
+            }
+        }
+
+        let stale: Vec<&(Item, usize)> = self
+            .input_buf
+            .iter()
+            .filter(|(item, _)| self.get_demanded(item.get_id()) == 0)
+            .collect();
+        if !stale.is_empty() {
+            params.ui.separator();
+            params.ui.text("not needed:");
+            for (item, amount) in stale {
+                render_item_gui(params, item, &format!("x{}", amount));
+            }
+        }
+    }
+}
+
+impl BuildingClone for Lab {
+    fn clone_box(&self) -> Box<dyn Building> {
+        Box::from(Lab {
+            name: self.name.clone(),
+            texture: self.texture,
+            facing: self.facing,
+            period: self.period,
+            from_last_consumption: 0,
+            input_buf: vec![],
+            demand: vec![],
+            consumed: vec![],
+        })
+    }
+}
+
+impl Building for Lab {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        self.input_buf.clone()
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        json!({
+            "from_last_consumption": self.from_last_consumption,
+            "input_buf": self
+                .input_buf
+                .iter()
+                .map(|(item, amount)| json!({ "item": item.get_name(), "amount": amount }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        self.from_last_consumption = JsonReader::read_u32(state, "from_last_consumption", error);
+        self.input_buf = JsonReader::read_vec(state, "input_buf", error)
+            .iter()
+            .map(|saved| {
+                let item_name = JsonReader::read_string(saved, "item", error);
+                let amount = JsonReader::read_u32(saved, "amount", error) as usize;
+                (
+                    item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name)),
+                    amount,
+                )
+            })
+            .collect();
+    }
+
+    // Items no longer needed can be taken back.
+    fn get_output_items(&self) -> Vec<(Item, usize)> {
+        self.input_buf
+            .iter()
+            .filter(|(item, _)| self.get_demanded(item.get_id()) == 0)
+            .cloned()
+            .collect()
+    }
+
+    fn take_output_items(&mut self, item_id: ItemId, amount: usize) -> usize {
+        if self.get_demanded(item_id) > 0 {
+            return 0;
+        }
+        self.take_items(item_id, amount)
+    }
+
+    fn put_input_items(&mut self, item: &Item, amount: usize) -> usize {
+        let amount = amount.min(self.get_free_space(item.get_id()));
+        if amount > 0 {
+            self.put_items(item, amount);
+        }
+        amount
+    }
+
+    fn as_lab_mut(&mut self) -> Option<&mut dyn LabBuilding> {
+        Some(self)
+    }
+}
+
+impl LabBuilding for Lab {
+    fn set_research_demand(&mut self, demand: &[(Item, usize)]) {
+        self.demand = demand.to_vec();
+    }
+
+    fn take_research_items(&mut self) -> Vec<(Item, usize)> {
+        std::mem::take(&mut self.consumed)
+    }
+}
+
+impl MessageSender for Lab {
+    fn pull_messages(&mut self, _tick_id: u32) -> Vec<Message> {
+        Vec::new()
+    }
+}
+
+impl MessageReceiver for Lab {
+    // Accepts from any side.
+    fn try_push_message(&mut self, message: Message) -> Option<Message> {
+        match &message {
+            Message::FieldMessage(msg) => match &msg.body {
+                field_message::MessageBody::PushItem(item)
+                    if !msg.refund && self.get_free_space(item.get_id()) > 0 =>
+                {
+                    self.put_items(item.get_item(), 1);
+                    None
+                }
+                field_message::MessageBody::PushItem(_) => Some(message),
+            },
+        }
+    }
+}
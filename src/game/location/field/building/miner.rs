@@ -1,15 +1,25 @@
 use std::collections::HashMap;
 
+use serde_json::json;
+
 use crate::game::{
     common::{
         asset_manager::{AssetId, AssetManager},
+        direction::Direction,
         json_reader::JsonReader,
     },
     game_entity::GameEntity,
     gui::with_gui::*,
+    location::event::BuildingEvent,
     location::field::{
-        building::{item::ItemFactory, recycler::Recycler, Building, BuildingClone},
+        building::{
+            item::{Item, ItemFactory, ItemId},
+            recycler::Recycler,
+            Building, BuildingClone,
+        },
         cell::surface::*,
+        fluid::{FluidPortMode, FluidTank},
+        power::ElectricPort,
     },
     message::*,
     renderer::{Renderer, Sprite},
@@ -19,9 +29,16 @@ use crate::game::{
 pub struct Miner {
     name: String,
     texture: AssetId,
+    // Recyclers take the sides from it.
+    facing: Direction,
 
     surface_recyclers: HashMap<SurfaceId, Box<Recycler>>,
+    surface_names: HashMap<SurfaceId, String>,
+    curr_surface: Option<SurfaceId>,
     curr_recycler: Option<Box<Recycler>>,
+
+    // Ore left in the cell, None if it's unlimited.
+    deposit: Option<u32>,
 }
 
 impl GameEntity for Miner {
@@ -34,12 +51,17 @@ impl GameEntity for Miner {
         }
     }
 
-    fn tick(&mut self, tick_id: u32) {
-        match &mut self.curr_recycler {
-            Some(recycler) => {
-                recycler.tick(tick_id);
+    // Ore of the whole production is taken from the deposit when it starts,
+    // so it doesn't start if the deposit can't cover it.
+    fn tick(&mut self, _tick_id: u32) {
+        if let Some(recycler) = &mut self.curr_recycler {
+            let amount = recycler.get_production_amount();
+            let can_start = !matches!(self.deposit, Some(deposit) if deposit < amount);
+            if recycler.tick_production(can_start) {
+                if let Some(deposit) = &mut self.deposit {
+                    *deposit -= amount;
+                }
             }
-            None => {}
         }
     }
 
@@ -50,7 +72,17 @@ impl GameEntity for Miner {
 }
 
 impl WithGui for Miner {
-    fn render_gui(&mut self, params: &mut GuiRenderParams) {}
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        match self.deposit {
+            Some(deposit) => params.ui.text(format!("deposit: {}", deposit)),
+            None => params.ui.text("deposit: unlimited"),
+        }
+
+        match &mut self.curr_recycler {
+            Some(recycler) => recycler.render_gui(params),
+            None => params.ui.text("nothing to mine"),
+        }
+    }
 }
 
 impl BuildingClone for Miner {
@@ -74,9 +106,14 @@ impl BuildingClone for Miner {
         Box::from(Miner {
             name: self.name.clone(),
             texture: self.texture,
+            facing: self.facing,
 
             surface_recyclers,
+            surface_names: self.surface_names.clone(),
+            curr_surface: self.curr_surface,
             curr_recycler,
+
+            deposit: self.deposit,
         })
     }
 }
@@ -85,10 +122,150 @@ impl Building for Miner {
     fn get_name(&self) -> &str {
         self.name.as_str()
     }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+        if let Some(recycler) = &mut self.curr_recycler {
+            recycler.set_facing(facing);
+        }
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        let surface = match self.curr_surface {
+            Some(surface_id) => json!(self.surface_names.get(&surface_id).unwrap()),
+            None => serde_json::Value::Null,
+        };
+        let recycler = match &self.curr_recycler {
+            Some(recycler) => recycler.save_state(),
+            None => serde_json::Value::Null,
+        };
+
+        json!({ "surface": surface, "recycler": recycler, "deposit": self.deposit })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        match state.get("surface") {
+            None | Some(serde_json::Value::Null) => {
+                self.curr_surface = None;
+                self.curr_recycler = None;
+            }
+            Some(_) => {
+                let surface_name = JsonReader::read_string(state, "surface", error);
+                self.init(SurfaceFactory::get_surface_id_by_name(&surface_name));
+
+                let recycler_state = JsonReader::read_obj(state, "recycler", error);
+                match &mut self.curr_recycler {
+                    Some(recycler) => recycler.load_state(&recycler_state, item_factory, error),
+                    None => *error = true,
+                }
+            }
+        }
+
+        self.deposit = match state.get("deposit") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(_) => Some(JsonReader::read_u32(state, "deposit", error)),
+        };
+    }
+
+    fn on_build(&mut self, surface: &Surface, deposit: Option<u32>) -> Result<(), String> {
+        if !self.surface_recyclers.contains_key(&surface.get_id()) {
+            return Err(format!(
+                "Miner {} can't mine surface {}",
+                self.name,
+                surface.get_name()
+            ));
+        }
+
+        self.init(surface.get_id());
+        self.deposit = deposit;
+        Ok(())
+    }
+
+    fn get_deposit(&self) -> Option<u32> {
+        self.deposit
+    }
+
+    fn take_events(&mut self) -> Vec<BuildingEvent> {
+        match &mut self.curr_recycler {
+            Some(recycler) => recycler.take_events(),
+            None => vec![],
+        }
+    }
+
+    fn get_output_items(&self) -> Vec<(Item, usize)> {
+        match &self.curr_recycler {
+            Some(recycler) => recycler.get_output_items(),
+            None => vec![],
+        }
+    }
+
+    fn take_output_items(&mut self, item_id: ItemId, amount: usize) -> usize {
+        match &mut self.curr_recycler {
+            Some(recycler) => recycler.take_output_items(item_id, amount),
+            None => 0,
+        }
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        match &self.curr_recycler {
+            Some(recycler) => recycler.get_contents(),
+            None => vec![],
+        }
+    }
+
+    fn get_fluid_tanks(&self) -> &[FluidTank] {
+        match &self.curr_recycler {
+            Some(recycler) => recycler.get_fluid_tanks(),
+            None => &[],
+        }
+    }
+
+    fn get_fluid_tanks_mut(&mut self) -> &mut [FluidTank] {
+        match &mut self.curr_recycler {
+            Some(recycler) => recycler.get_fluid_tanks_mut(),
+            None => &mut [],
+        }
+    }
+
+    fn get_fluid_sides(&self, mode: FluidPortMode) -> Vec<Direction> {
+        match &self.curr_recycler {
+            Some(recycler) => recycler.get_fluid_sides(mode),
+            None => vec![],
+        }
+    }
+
+    fn get_electric_ports(&self) -> &[ElectricPort] {
+        match &self.curr_recycler {
+            Some(recycler) => recycler.get_electric_ports(),
+            None => &[],
+        }
+    }
+
+    fn is_electric_active(&self) -> bool {
+        match &self.curr_recycler {
+            Some(recycler) => recycler.is_electric_active(),
+            None => false,
+        }
+    }
+
+    fn set_power_satisfaction(&mut self, satisfaction: f32) {
+        if let Some(recycler) = &mut self.curr_recycler {
+            recycler.set_power_satisfaction(satisfaction);
+        }
+    }
 }
 
 impl Miner {
-    pub fn from_json_object(obj: &serde_json::Value) -> Miner {
+    pub fn from_json_object(obj: &serde_json::Value, item_factory: &ItemFactory) -> Miner {
         let mut error = false;
 
         let name = JsonReader::read_string(obj, "name", &mut error);
@@ -97,14 +274,17 @@ impl Miner {
         let texture = AssetManager::get_asset_id(&tex_path);
 
         let mut surface_recyclers = HashMap::new();
+        let mut surface_names = HashMap::new();
         let surfaces = JsonReader::read_vec(obj, "surfaces", &mut error);
 
         for surface_obj in surfaces {
             let surface_name = JsonReader::read_string(&surface_obj, "surface", &mut error);
             let recycler_obj = JsonReader::read_obj(&surface_obj, "recycler", &mut error);
-            let recycler = Recycler::from_json_object(&recycler_obj);
+            let mut recycler = Recycler::from_json_object(&recycler_obj);
+            recycler.init_items(item_factory);
             let surface_id = SurfaceFactory::get_surface_id_by_name(surface_name.as_str());
             surface_recyclers.insert(surface_id, Box::from(recycler));
+            surface_names.insert(surface_id, surface_name);
         }
 
         if error {
@@ -123,18 +303,24 @@ impl Miner {
         Miner {
             name,
             texture,
+            facing: Direction::Up,
 
             surface_recyclers,
+            surface_names,
+            curr_surface: None,
             curr_recycler: None,
+
+            deposit: None,
         }
     }
 
-    pub fn init(&mut self, surface_id: SurfaceId, item_factory: &ItemFactory) {
+    pub fn init(&mut self, surface_id: SurfaceId) {
+        self.curr_surface = Some(surface_id).filter(|id| self.surface_recyclers.contains_key(id));
         self.curr_recycler = match self.surface_recyclers.get(&surface_id) {
             Some(recycler) => {
                 let mut cloned =
                     unsafe { Box::from_raw(Box::into_raw(recycler.clone_box()) as *mut Recycler) };
-                (&mut *cloned).init_items(item_factory);
+                cloned.set_facing(self.facing);
                 Some(cloned)
             }
             None => None,
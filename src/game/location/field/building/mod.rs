@@ -1,11 +1,115 @@
-use crate::game::{game_entity::*, gui::with_gui::*, message::*, renderer::Renderer};
+use crate::game::{
+    common::{
+        direction::Direction,
+        json_reader::JsonReader,
+        math::{IVec2, Vec2},
+    },
+    game_entity::*,
+    gui::with_gui::*,
+    message::*,
+    renderer::Renderer,
+};
 
+use crate::game::location::event::BuildingEvent;
+
+use super::{
+    cell::surface::Surface,
+    fluid::{FluidPortMode, FluidTank},
+    power::{ElectricPort, ADJACENT_RANGE},
+};
+use item::{Item, ItemFactory, ItemId};
+
+pub mod belt_router;
+pub mod building_registry;
 pub mod craft_station;
+pub mod electric_pole;
 pub mod error_building;
+pub mod inserter;
 pub mod item;
+pub mod item_sides;
+pub mod lab;
 pub mod miner;
+pub mod pipe;
 pub mod recycler;
+pub mod storage_chest;
 pub mod transport_belt;
+pub mod underground_belt;
+
+// Max footprint side(in cells), bounds the area to search for buildings covering a cell.
+pub const MAX_BUILDING_SIZE: isize = 4;
+
+// Optional "size" of the footprint, 1x1 by default.
+pub fn size_from_json_object(obj: &serde_json::Value, error: &mut bool) -> IVec2 {
+    if obj.get("size").is_none() {
+        return IVec2::new(1, 1);
+    }
+
+    let size = JsonReader::read_ivec2(obj, "size", error);
+    if size.x < 1 || size.y < 1 || size.x > MAX_BUILDING_SIZE || size.y > MAX_BUILDING_SIZE {
+        log::error!("Building size {} is out of range", size);
+        *error = true;
+        return IVec2::new(1, 1);
+    }
+    size
+}
+
+// Footprint turned sideways swaps its width and height.
+pub fn rotate_size(size: IVec2, facing: Direction) -> IVec2 {
+    match facing {
+        Direction::Left | Direction::Right => IVec2::new(size.y, size.x),
+        _ => size,
+    }
+}
+
+// Distance between the closest cells of two footprints, neighbours are 1 apart.
+pub fn footprint_distance(
+    origin: IVec2,
+    size: IVec2,
+    other_origin: IVec2,
+    other_size: IVec2,
+) -> u32 {
+    let gap = |min: isize, size: isize, other_min: isize, other_size: isize| {
+        (other_min - (min + size - 1))
+            .max(min - (other_min + other_size - 1))
+            .max(0)
+    };
+    (gap(origin.x, size.x, other_origin.x, other_size.x)
+        + gap(origin.y, size.y, other_origin.y, other_size.y)) as u32
+}
+
+// Multi-cell building sprite is stretched over the footprint from the origin cell.
+pub fn footprint_transform(size: IVec2) -> SpriteTransform {
+    let size = size.to_vec2();
+    SpriteTransform::default()
+        .add_translation((size - Vec2::new_xy(1.0)) * 0.5)
+        .add_scale(size)
+}
+
+// Item icon with a label on the same line.
+pub fn render_item_gui(params: &mut GuiRenderParams, item: &Item, label: &str) {
+    imgui::Image::new(
+        params.get_or_load_texture_id(item.get_sprite_asset_id()),
+        [20.0, 20.0],
+    )
+    .build(params.ui);
+    params.ui.same_line();
+    params.ui.text(label);
+}
+
+// Optional "facing", Up by default.
+pub fn facing_from_json_object(obj: &serde_json::Value, error: &mut bool) -> Direction {
+    if obj.get("facing").is_none() {
+        return Direction::Up;
+    }
+
+    match Direction::from_name(&JsonReader::read_string(obj, "facing", error)) {
+        Some(Direction::None) | None => {
+            *error = true;
+            Direction::Up
+        }
+        Some(facing) => facing,
+    }
+}
 
 pub trait BuildingClone {
     fn clone_box(&self) -> Box<dyn Building>;
@@ -13,4 +117,175 @@ pub trait BuildingClone {
 
 pub trait Building: GameEntity + BuildingClone + MessageReceiver + MessageSender + WithGui {
     fn get_name(&self) -> &str;
+
+    // Footprint in cells for the current facing, extends from the origin cell to positive x and y.
+    fn get_size(&self) -> IVec2 {
+        IVec2::new(1, 1)
+    }
+
+    // Up keeps the sides and footprint as they are declared in the dictionary.
+    fn get_facing(&self) -> Direction;
+    fn set_facing(&mut self, facing: Direction);
+
+    // Runtime state only, static data is restored from the dictionary by name.
+    fn save_state(&self) -> serde_json::Value;
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    );
+
+    // Called when built into a cell, error message refuses placement.
+    fn on_build(&mut self, _surface: &Surface, _deposit: Option<u32>) -> Result<(), String> {
+        Ok(())
+    }
+
+    // Ore left in the cell for the buildings mining it.
+    fn get_deposit(&self) -> Option<u32> {
+        None
+    }
+
+    // Placement-time configuration, e.g. from a scenario file.
+    fn load_config(&mut self, _config: &serde_json::Value, error: &mut bool) {
+        log::error!("Building {} can't be configured", self.get_name());
+        *error = true;
+    }
+
+    // Buffered items the player can take.
+    fn get_output_items(&self) -> Vec<(Item, usize)> {
+        vec![]
+    }
+
+    // Returns the amount taken.
+    fn take_output_items(&mut self, _item_id: ItemId, _amount: usize) -> usize {
+        0
+    }
+
+    // Everything held inside, refunded along with the cost on deconstruction.
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        vec![]
+    }
+
+    // Items put by the player, returns the amount accepted.
+    fn put_input_items(&mut self, _item: &Item, _amount: usize) -> usize {
+        0
+    }
+
+    fn as_tunnel(&self) -> Option<&dyn TunnelBuilding> {
+        None
+    }
+
+    fn as_tunnel_mut(&mut self) -> Option<&mut dyn TunnelBuilding> {
+        None
+    }
+
+    fn as_belt(&self) -> Option<&dyn BeltBuilding> {
+        None
+    }
+
+    fn as_belt_mut(&mut self) -> Option<&mut dyn BeltBuilding> {
+        None
+    }
+
+    fn as_inserter(&self) -> Option<&dyn InserterBuilding> {
+        None
+    }
+
+    fn as_inserter_mut(&mut self) -> Option<&mut dyn InserterBuilding> {
+        None
+    }
+
+    // Fluids held by the building, flowing to the neighbours holding the same fluids.
+    fn get_fluid_tanks(&self) -> &[FluidTank] {
+        &[]
+    }
+
+    fn get_fluid_tanks_mut(&mut self) -> &mut [FluidTank] {
+        &mut []
+    }
+
+    // Absolute sides the tanks of the mode connect through, all the sides by default.
+    fn get_fluid_sides(&self, _mode: FluidPortMode) -> Vec<Direction> {
+        vec![
+            Direction::Up,
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+        ]
+    }
+
+    fn get_electric_ports(&self) -> &[ElectricPort] {
+        &[]
+    }
+
+    // Max distance(in cells) to the ports of the same voltage to be connected with.
+    fn get_electric_range(&self) -> u32 {
+        ADJACENT_RANGE
+    }
+
+    // Whether ports are consuming or producing energy now.
+    fn is_electric_active(&self) -> bool {
+        false
+    }
+
+    fn set_power_satisfaction(&mut self, _satisfaction: f32) {}
+
+    fn as_lab_mut(&mut self) -> Option<&mut dyn LabBuilding> {
+        None
+    }
+
+    fn as_recipe_lock_mut(&mut self) -> Option<&mut dyn RecipeLockBuilding> {
+        None
+    }
+
+    // Events raised since the last call.
+    fn take_events(&mut self) -> Vec<BuildingEvent> {
+        vec![]
+    }
+}
+
+// Underground belt end, linked with the other end by the field.
+pub trait TunnelBuilding {
+    // Max distance(in cells) to the underground exit ahead, for the entrances only.
+    fn get_tunnel_range(&self) -> Option<u32>;
+
+    fn is_tunnel_exit(&self) -> bool;
+
+    // Cell of the linked exit, set by the field every tick.
+    fn set_tunnel_exit(&mut self, exit: Option<IVec2>);
+}
+
+// Carries items in slots, inserter arms can pick them up from there.
+pub trait BeltBuilding {
+    // One entry per occupied slot.
+    fn get_slot_items(&self) -> Vec<Item>;
+
+    // Takes the first slot holding the item, returns whether there was one.
+    fn take_slot_item(&mut self, item_id: ItemId) -> bool;
+}
+
+// Arm moving single items, fed by the field from the building behind.
+pub trait InserterBuilding {
+    // Empty hand behind, the field offers it the items of the building there.
+    fn is_waiting_to_pick_up(&self) -> bool;
+
+    fn accepts_pick_up(&self, item: &Item) -> bool;
+
+    fn pick_up(&mut self, item: Item);
+}
+
+// Pays for the current research with the items put in.
+pub trait LabBuilding {
+    // Items left to pay for the current research, set by the field every tick.
+    fn set_research_demand(&mut self, demand: &[(Item, usize)]);
+
+    // Items consumed for the research since the last call.
+    fn take_research_items(&mut self) -> Vec<(Item, usize)>;
+}
+
+// Has recipes unlocked by the research.
+pub trait RecipeLockBuilding {
+    // Recipes of the technologies not researched yet, set by the field every tick.
+    fn set_locked_recipes(&mut self, locked: &[String]);
 }
@@ -0,0 +1,143 @@
+use crate::game::{
+    common::{
+        asset_manager::{AssetId, AssetManager},
+        direction::Direction,
+        json_reader::JsonReader,
+    },
+    location::field::fluid::{FluidFactory, FluidPortMode, FluidTank},
+    renderer::Sprite,
+};
+
+use super::{item::ItemFactory, *};
+
+// Pipe or storage tank, holds up to the capacity of each fluid.
+pub struct Pipe {
+    name: String,
+    texture: AssetId,
+    facing: Direction,
+
+    // One per fluid in the dictionary.
+    tanks: Vec<FluidTank>,
+}
+
+impl Pipe {
+    pub fn from_json_object(obj: &serde_json::Value, fluid_factory: &FluidFactory) -> Pipe {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let tex_path = JsonReader::read_string(obj, "texture", &mut error);
+        let capacity = JsonReader::read_u32(obj, "capacity", &mut error);
+
+        if error {
+            log::error!(
+                "Failed to parse Pipe from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("Pipe succesfully loaded({})", name);
+        }
+
+        Pipe {
+            name,
+            texture: AssetManager::get_asset_id(&tex_path),
+            facing: Direction::Up,
+            tanks: fluid_factory
+                .get_sorted_fluids()
+                .iter()
+                .map(|fluid| FluidTank::new(fluid.clone(), FluidPortMode::Both, capacity))
+                .collect(),
+        }
+    }
+}
+
+impl GameEntity for Pipe {
+    fn update(&mut self, _parameters: &UpdateParameters) {}
+
+    fn tick(&mut self, _tick_id: u32) {}
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let sprite = Sprite::new(self.texture);
+        renderer.queue_render_sprite(sprite, transform);
+    }
+}
+
+impl WithGui for Pipe {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        for tank in &self.tanks {
+            params.ui.text(tank.fluid.get_name());
+            imgui::ProgressBar::new(tank.volume as f32 / tank.capacity.max(1) as f32)
+                .overlay_text(format!("{}/{}", tank.volume, tank.capacity))
+                .size([200.0, 0.0])
+                .build(params.ui);
+        }
+    }
+}
+
+impl BuildingClone for Pipe {
+    fn clone_box(&self) -> Box<dyn Building> {
+        let mut tanks = self.tanks.clone();
+        for tank in tanks.iter_mut() {
+            tank.volume = 0;
+        }
+
+        Box::from(Pipe {
+            name: self.name.clone(),
+            texture: self.texture,
+            facing: self.facing,
+            tanks,
+        })
+    }
+}
+
+impl Building for Pipe {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "fluid_tanks": FluidTank::save_tanks(&self.tanks),
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        _item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        FluidTank::load_tanks(&mut self.tanks, state, "fluid_tanks", error);
+    }
+
+    fn get_fluid_tanks(&self) -> &[FluidTank] {
+        &self.tanks
+    }
+
+    fn get_fluid_tanks_mut(&mut self) -> &mut [FluidTank] {
+        &mut self.tanks
+    }
+}
+
+impl MessageSender for Pipe {
+    fn pull_messages(&mut self, _tick_id: u32) -> Vec<Message> {
+        Vec::new()
+    }
+}
+
+impl MessageReceiver for Pipe {
+    fn try_push_message(&mut self, message: Message) -> Option<Message> {
+        Some(message)
+    }
+}
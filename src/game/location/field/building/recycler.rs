@@ -1,5 +1,7 @@
 use std::collections::HashMap;
 
+use serde_json::json;
+
 use super::*;
 
 use crate::game::{
@@ -7,21 +9,41 @@ use crate::game::{
         asset_manager::{AssetId, AssetManager},
         direction::Direction,
         json_reader::JsonReader,
+        math::IVec2,
     },
     game_entity::GameEntity,
-    location::field::building::item::{Item, ItemFactory, ItemId, TransportedItem},
+    location::event::BuildingEvent,
+    location::field::{
+        building::{
+            item::{Item, ItemFactory, ItemId, TransportedItem},
+            item_sides::ItemSides,
+        },
+        fluid::{Fluid, FluidPortMode, FluidTank},
+        power::ElectricPort,
+    },
     message::*,
     renderer::{Renderer, Sprite},
 };
 
+// Compensates float error when satisfaction is a fraction like 1/3.
+const POWER_PROGRESS_EPSILON: f32 = 1e-4;
+
 pub struct Recycler {
     name: String,
     texture: AssetId,
+    size: IVec2,
+    facing: Direction,
+    item_sides: ItemSides,
 
     period: u32,
     from_last_production: u32,
     can_produce: bool,
 
+    electric_ports: Vec<ElectricPort>,
+    // Production goes slower when consumed energy isn't fully provided.
+    power_satisfaction: f32,
+    power_progress: f32,
+
     // Items.
     item_input: HashMap<ItemId, u32>,
     item_output: HashMap<ItemId, u32>,
@@ -30,6 +52,12 @@ pub struct Recycler {
     item_output_buf: HashMap<ItemId, u32>,
 
     item_prototypes: HashMap<ItemId, Item>,
+
+    // Fluids, sized for one production.
+    fluid_tanks: Vec<FluidTank>,
+
+    // Taken by the field at the end of the tick.
+    events: Vec<BuildingEvent>,
 }
 
 impl Recycler {
@@ -38,22 +66,40 @@ impl Recycler {
 
         let tex_path = JsonReader::read_string(obj, "texture", error);
         self.texture = AssetManager::get_asset_id(&tex_path);
+        self.size = size_from_json_object(obj, error);
+        self.item_sides = ItemSides::from_json_object(obj, error);
 
         self.period = JsonReader::read_i32(obj, "period", error) as u32;
     }
 
-    fn item_data_from_json_object(&mut self, obj: &serde_json::Value, error: &mut bool) {
-        let items = JsonReader::read_obj(obj, "items", error);
-
-        let item_input_vec = JsonReader::read_vec(&items, "input", error);
-        let item_input_vec: Vec<(String, u32)> = item_input_vec
+    // Entries with a "fluid" instead of an "item" go to the fluid tanks.
+    fn read_items_and_fluids(
+        &mut self,
+        items: &serde_json::Value,
+        name: &str,
+        mode: FluidPortMode,
+        error: &mut bool,
+    ) -> Vec<(String, u32)> {
+        JsonReader::read_vec(items, name, error)
             .iter()
-            .map(|item| {
-                let name = JsonReader::read_string(item, "item", error);
+            .filter_map(|item| {
                 let amount = JsonReader::read_i32(item, "amount", error) as u32;
-                (name, amount)
+                if item.get("fluid").is_some() {
+                    let fluid = Fluid::new(&JsonReader::read_string(item, "fluid", error));
+                    self.fluid_tanks.push(FluidTank::new(fluid, mode, amount));
+                    return None;
+                }
+                let name = JsonReader::read_string(item, "item", error);
+                Some((name, amount))
             })
-            .collect();
+            .collect()
+    }
+
+    fn item_data_from_json_object(&mut self, obj: &serde_json::Value, error: &mut bool) {
+        let items = JsonReader::read_obj(obj, "items", error);
+
+        self.fluid_tanks = vec![];
+        let item_input_vec = self.read_items_and_fluids(&items, "input", FluidPortMode::In, error);
 
         self.item_input = HashMap::new();
         self.item_input_buf = HashMap::new();
@@ -63,15 +109,8 @@ impl Recycler {
             self.item_input_buf.insert(id, 0);
         }
 
-        let item_output_vec = JsonReader::read_vec(&items, "output", error);
-        let item_output_vec: Vec<(String, u32)> = item_output_vec
-            .iter()
-            .map(|item| {
-                let name = JsonReader::read_string(item, "item", error);
-                let amount = JsonReader::read_i32(item, "amount", error) as u32;
-                (name, amount)
-            })
-            .collect();
+        let item_output_vec =
+            self.read_items_and_fluids(&items, "output", FluidPortMode::Out, error);
 
         self.item_output = HashMap::new();
         self.item_output_buf = HashMap::new();
@@ -82,26 +121,44 @@ impl Recycler {
         }
     }
 
+    fn electric_data_from_json_object(&mut self, obj: &serde_json::Value, error: &mut bool) {
+        self.electric_ports = JsonReader::read_vec(obj, "electric_ports", error)
+            .iter()
+            .map(|port| ElectricPort::from_json_object(port, error))
+            .collect();
+    }
+
     pub fn from_json_object(obj: &serde_json::Value) -> Recycler {
         let mut recycler = Recycler {
             name: String::new(),
             texture: AssetId::null(),
+            size: IVec2::new(1, 1),
+            facing: Direction::Up,
+            item_sides: ItemSides::default(),
 
             period: 0,
             from_last_production: 0,
             can_produce: false,
 
+            electric_ports: vec![],
+            power_satisfaction: 1.0,
+            power_progress: 0.0,
+
             item_input: HashMap::new(),
             item_output: HashMap::new(),
             item_input_buf: HashMap::new(),
             item_output_buf: HashMap::new(),
             item_prototypes: HashMap::new(),
+
+            fluid_tanks: vec![],
+            events: vec![],
         };
 
         let mut error = false;
 
         recycler.common_data_from_json_object(obj, &mut error);
         recycler.item_data_from_json_object(obj, &mut error);
+        recycler.electric_data_from_json_object(obj, &mut error);
 
         if error {
             log::error!(
@@ -128,56 +185,92 @@ impl Recycler {
         }
     }
 
-    fn pull_item_messages(&mut self, tick_id: u32) -> Vec<Message> {
-        let mut messages = Vec::new();
-        for item_id in self.item_output.keys() {
-            let item_count = *self.item_output_buf.get(item_id).unwrap();
-            let item_prototype = self.item_prototypes.get(item_id).unwrap();
+    fn save_item_buffer(&self, buffer: &HashMap<ItemId, u32>) -> serde_json::Value {
+        let mut items: Vec<(&str, u32)> = buffer
+            .iter()
+            .map(|(id, &amount)| match self.item_prototypes.get(id) {
+                Some(item) => (item.get_name(), amount),
+                None => {
+                    log::error!("Saving Recycler({}) with uninitialized items", self.name);
+                    ("error", amount)
+                }
+            })
+            .collect();
+        items.sort();
+
+        serde_json::Value::Array(
+            items
+                .into_iter()
+                .map(|(item, amount)| json!({ "item": item, "amount": amount }))
+                .collect(),
+        )
+    }
 
-            for _ in 0..item_count {
-                messages.push(Message::FieldMessage(field_message::Message {
-                    id: messages.len() as u32,
-                    sender: field_message::MessageExchangeActor::default(),
-                    receiver: field_message::MessageExchangeActor::default(),
-                    target: field_message::Target::Directions(vec![
-                        Direction::Up,
-                        Direction::Right,
-                        Direction::Down,
-                        Direction::Left,
-                    ]),
-                    tick_id,
-                    refund: false,
-                    body: field_message::MessageBody::PushItem(TransportedItem::new(
-                        item_prototype.clone(),
-                    )),
-                }));
+    fn load_item_buffer(
+        buffer: &mut HashMap<ItemId, u32>,
+        state: &serde_json::Value,
+        name: &str,
+        error: &mut bool,
+    ) {
+        for item in JsonReader::read_vec(state, name, error) {
+            let id =
+                ItemFactory::get_item_id_by_name(&JsonReader::read_string(&item, "item", error));
+            let amount = JsonReader::read_u32(&item, "amount", error);
+            match buffer.get_mut(&id) {
+                Some(buffered) => *buffered = amount,
+                None => *error = true,
             }
         }
-
-        for id in self.item_output.keys() {
-            *self.item_output_buf.get_mut(id).unwrap() = 0;
-        }
-
-        messages
     }
-}
 
-impl GameEntity for Recycler {
-    fn update(&mut self, parameters: &UpdateParameters) {}
+    // Sorted by name so that gui doesn't depend on HashMap order.
+    fn get_sorted_item_ids(&self, items: &HashMap<ItemId, u32>) -> Vec<ItemId> {
+        let mut item_ids: Vec<(&str, ItemId)> = items
+            .keys()
+            .filter_map(|&id| {
+                self.item_prototypes
+                    .get(&id)
+                    .map(|item| (item.get_name(), id))
+            })
+            .collect();
+        item_ids.sort_by(|a, b| a.0.cmp(b.0));
+        item_ids.into_iter().map(|(_, id)| id).collect()
+    }
 
-    fn tick(&mut self, tick_id: u32) {
+    // Next production starts only if allowed, returns whether it has started this tick.
+    pub fn tick_production(&mut self, can_start: bool) -> bool {
         if self.can_produce {
-            self.from_last_production += 1;
-            if self.from_last_production >= self.period {
-                for (id, &amount) in &self.item_output {
-                    *self.item_output_buf.get_mut(id).unwrap() = amount;
+            self.power_progress += self.power_satisfaction;
+            if self.power_progress >= 1.0 - POWER_PROGRESS_EPSILON {
+                self.power_progress = (self.power_progress - 1.0).max(0.0);
+                self.from_last_production += 1;
+            }
+            // Waits for the fluid outputs of the previous production to flow away.
+            let can_output = self
+                .get_fluid_tanks_by_mode(FluidPortMode::Out)
+                .all(|tank| tank.volume == 0);
+            if self.from_last_production >= self.period && can_output {
+                for id in self.get_sorted_item_ids(&self.item_output) {
+                    let amount = self.item_output[&id];
+                    *self.item_output_buf.get_mut(&id).unwrap() = amount;
+                    self.events.push(BuildingEvent::ItemProduced {
+                        item: self.item_prototypes[&id].clone(),
+                        amount: amount as usize,
+                    });
+                }
+                for tank in self.fluid_tanks.iter_mut() {
+                    if tank.mode == FluidPortMode::Out {
+                        tank.volume = tank.capacity;
+                    }
                 }
                 self.can_produce = false;
             }
         }
 
-        if !self.can_produce {
-            let mut can_take_resources = true;
+        if !self.can_produce && can_start {
+            let mut can_take_resources = self
+                .get_fluid_tanks_by_mode(FluidPortMode::In)
+                .all(FluidTank::is_full);
             for (item, &amount) in &self.item_input_buf {
                 if amount < *self.item_input.get(item).unwrap() {
                     can_take_resources = false;
@@ -189,11 +282,69 @@ impl GameEntity for Recycler {
                 for amount in self.item_input_buf.values_mut() {
                     *amount = 0;
                 }
+                for tank in self.fluid_tanks.iter_mut() {
+                    if tank.mode == FluidPortMode::In {
+                        tank.volume = 0;
+                    }
+                }
 
                 self.can_produce = true;
                 self.from_last_production = 0;
+                return true;
             }
         }
+        false
+    }
+
+    // Items made by a single production.
+    pub fn get_production_amount(&self) -> u32 {
+        self.item_output.values().sum()
+    }
+
+    fn get_fluid_tanks_by_mode(&self, mode: FluidPortMode) -> impl Iterator<Item = &FluidTank> {
+        self.fluid_tanks
+            .iter()
+            .filter(move |tank| tank.mode == mode)
+    }
+
+    // Sorted by name so that message ids don't depend on HashMap order.
+    fn pull_item_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        let mut messages = Vec::new();
+        for item_id in self.get_sorted_item_ids(&self.item_output) {
+            let item_count = *self.item_output_buf.get(&item_id).unwrap();
+            let item_prototype = self.item_prototypes.get(&item_id).unwrap();
+
+            for _ in 0..item_count {
+                messages.push(Message::FieldMessage(field_message::Message {
+                    id: messages.len() as u32,
+                    sender: field_message::MessageExchangeActor::default(),
+                    receiver: field_message::MessageExchangeActor::default(),
+                    target: field_message::Target::Directions(
+                        self.item_sides.get_output_directions(self.facing),
+                    ),
+                    tick_id,
+                    refund: false,
+                    ground_cell: None,
+                    body: field_message::MessageBody::PushItem(TransportedItem::new(
+                        item_prototype.clone(),
+                    )),
+                }));
+            }
+        }
+
+        for id in self.item_output.keys() {
+            *self.item_output_buf.get_mut(id).unwrap() = 0;
+        }
+
+        messages
+    }
+}
+
+impl GameEntity for Recycler {
+    fn update(&mut self, parameters: &UpdateParameters) {}
+
+    fn tick(&mut self, _tick_id: u32) {
+        self.tick_production(true);
     }
 
     fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
@@ -203,7 +354,45 @@ impl GameEntity for Recycler {
 }
 
 impl WithGui for Recycler {
-    fn render_gui(&mut self, params: &mut GuiRenderParams) {}
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        if self.can_produce {
+            imgui::ProgressBar::new(self.from_last_production as f32 / self.period.max(1) as f32)
+                .size([200.0, 0.0])
+                .build(params.ui);
+        } else {
+            params.ui.text("waiting for inputs");
+        }
+
+        let mut fluid_inputs = self.get_fluid_tanks_by_mode(FluidPortMode::In).peekable();
+        if !self.item_input.is_empty() || fluid_inputs.peek().is_some() {
+            params.ui.text("inputs:");
+            for id in self.get_sorted_item_ids(&self.item_input) {
+                let label = format!("{}/{}", self.item_input_buf[&id], self.item_input[&id]);
+                render_item_gui(params, &self.item_prototypes[&id], &label);
+            }
+            for tank in fluid_inputs {
+                params.ui.text(format!(
+                    "{} {}/{}",
+                    tank.fluid.get_name(),
+                    tank.volume,
+                    tank.capacity
+                ));
+            }
+        }
+        let mut fluid_outputs = self.get_fluid_tanks_by_mode(FluidPortMode::Out).peekable();
+        if !self.item_output.is_empty() || fluid_outputs.peek().is_some() {
+            params.ui.text("outputs:");
+            for id in self.get_sorted_item_ids(&self.item_output) {
+                let label = format!("x{}", self.item_output_buf[&id]);
+                render_item_gui(params, &self.item_prototypes[&id], &label);
+            }
+            for tank in fluid_outputs {
+                params
+                    .ui
+                    .text(format!("{} x{}", tank.fluid.get_name(), tank.volume));
+            }
+        }
+    }
 }
 
 impl BuildingClone for Recycler {
@@ -216,15 +405,26 @@ impl BuildingClone for Recycler {
         for val in item_output_buf.values_mut() {
             *val = 0;
         }
+        let mut fluid_tanks = self.fluid_tanks.clone();
+        for tank in fluid_tanks.iter_mut() {
+            tank.volume = 0;
+        }
 
         Box::from(Recycler {
             name: self.name.clone(),
             texture: self.texture,
+            size: self.size,
+            facing: self.facing,
+            item_sides: self.item_sides.clone(),
 
             period: self.period,
             from_last_production: 0,
             can_produce: false,
 
+            electric_ports: self.electric_ports.clone(),
+            power_satisfaction: 1.0,
+            power_progress: 0.0,
+
             item_input: self.item_input.clone(),
             item_output: self.item_output.clone(),
 
@@ -232,6 +432,9 @@ impl BuildingClone for Recycler {
             item_output_buf,
 
             item_prototypes: self.item_prototypes.clone(),
+
+            fluid_tanks,
+            events: vec![],
         })
     }
 }
@@ -240,6 +443,139 @@ impl Building for Recycler {
     fn get_name(&self) -> &str {
         &self.name
     }
+
+    fn get_size(&self) -> IVec2 {
+        rotate_size(self.size, self.facing)
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    // Inputs of the production in progress are counted too.
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        let mut amounts = self.item_output_buf.clone();
+        for (&id, &amount) in &self.item_input_buf {
+            *amounts.entry(id).or_insert(0) += amount;
+        }
+        if self.can_produce {
+            for (&id, &amount) in &self.item_input {
+                *amounts.entry(id).or_insert(0) += amount;
+            }
+        }
+
+        self.get_sorted_item_ids(&amounts)
+            .into_iter()
+            .filter(|id| amounts[id] > 0)
+            .map(|id| (self.item_prototypes[&id].clone(), amounts[&id] as usize))
+            .collect()
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        json!({
+            "from_last_production": self.from_last_production,
+            "can_produce": self.can_produce,
+            "power_progress": self.power_progress,
+            "item_input_buf": self.save_item_buffer(&self.item_input_buf),
+            "item_output_buf": self.save_item_buffer(&self.item_output_buf),
+            "fluid_tanks": FluidTank::save_tanks(&self.fluid_tanks),
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        self.init_items(item_factory);
+
+        self.from_last_production = JsonReader::read_u32(state, "from_last_production", error);
+        self.can_produce = JsonReader::read_bool(state, "can_produce", error);
+        self.power_progress = JsonReader::read_f32(state, "power_progress", error);
+        Self::load_item_buffer(&mut self.item_input_buf, state, "item_input_buf", error);
+        Self::load_item_buffer(&mut self.item_output_buf, state, "item_output_buf", error);
+        FluidTank::load_tanks(&mut self.fluid_tanks, state, "fluid_tanks", error);
+    }
+
+    fn get_output_items(&self) -> Vec<(Item, usize)> {
+        self.get_sorted_item_ids(&self.item_output_buf)
+            .into_iter()
+            .filter(|id| self.item_output_buf[id] > 0)
+            .map(|id| {
+                (
+                    self.item_prototypes[&id].clone(),
+                    self.item_output_buf[&id] as usize,
+                )
+            })
+            .collect()
+    }
+
+    fn take_output_items(&mut self, item_id: ItemId, amount: usize) -> usize {
+        match self.item_output_buf.get_mut(&item_id) {
+            Some(buffered) => {
+                let taken = amount.min(*buffered as usize);
+                *buffered -= taken as u32;
+                taken
+            }
+            None => 0,
+        }
+    }
+
+    // Input buffers hold the amount needed for one production.
+    fn put_input_items(&mut self, item: &Item, amount: usize) -> usize {
+        let item_id = item.get_id();
+        match (
+            self.item_input_buf.get_mut(&item_id),
+            self.item_input.get(&item_id),
+        ) {
+            (Some(buffered), Some(&needed)) => {
+                let accepted = amount.min(needed.saturating_sub(*buffered) as usize);
+                *buffered += accepted as u32;
+                accepted
+            }
+            _ => 0,
+        }
+    }
+
+    fn get_fluid_tanks(&self) -> &[FluidTank] {
+        &self.fluid_tanks
+    }
+
+    fn get_fluid_tanks_mut(&mut self) -> &mut [FluidTank] {
+        &mut self.fluid_tanks
+    }
+
+    // Fluids go through the same sides as the items.
+    fn get_fluid_sides(&self, mode: FluidPortMode) -> Vec<Direction> {
+        let inputs = self.item_sides.get_input_directions(self.facing);
+        let outputs = self.item_sides.get_output_directions(self.facing);
+        match mode {
+            FluidPortMode::In => inputs,
+            FluidPortMode::Out => outputs,
+            FluidPortMode::Both => [inputs, outputs].concat(),
+        }
+    }
+
+    fn take_events(&mut self) -> Vec<BuildingEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn get_electric_ports(&self) -> &[ElectricPort] {
+        &self.electric_ports
+    }
+
+    fn is_electric_active(&self) -> bool {
+        self.can_produce
+    }
+
+    fn set_power_satisfaction(&mut self, satisfaction: f32) {
+        self.power_satisfaction = satisfaction;
+    }
 }
 
 impl MessageSender for Recycler {
@@ -273,6 +609,13 @@ impl MessageReceiver for Recycler {
                         *self.item_output_buf.get_mut(&item.get_id()).unwrap() += 1;
                         None
                     } else {
+                        let direction = Direction::from_ivec2(
+                            msg.sender.get_position() - msg.receiver.get_position(),
+                        );
+                        if !self.item_sides.accepts_from(direction, self.facing) {
+                            return Some(message);
+                        }
+
                         let item_id = item.get_id();
                         if self.item_input.contains_key(&item_id) {
                             let inp_buf = self.item_input_buf.get_mut(&item_id).unwrap();
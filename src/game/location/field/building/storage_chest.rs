@@ -0,0 +1,261 @@
+use serde_json::json;
+
+use crate::game::{
+    common::{
+        asset_manager::{AssetId, AssetManager},
+        direction::Direction,
+        json_reader::JsonReader,
+    },
+    location::player::inventory::Inventory,
+    renderer::Sprite,
+};
+
+use super::{
+    item::{Item, ItemFactory, ItemId, TransportedItem},
+    *,
+};
+
+// Drag and drop payload type of the chest slots, so that they don't mix with the player ones.
+const CHEST_SLOT_PAYLOAD: &str = "chest slot";
+
+// Stores the items pushed from any side, optionally pushes them out through the output side.
+pub struct StorageChest {
+    name: String,
+    texture: AssetId,
+    facing: Direction,
+
+    inventory: Inventory,
+    // Relative to the facing, None keeps the items inside.
+    output: Option<Direction>,
+    // Pushed out this tick, room for it is kept until the next pull in case it's refunded.
+    sent_item: Option<Item>,
+}
+
+impl StorageChest {
+    pub fn from_json_object(obj: &serde_json::Value) -> StorageChest {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let tex_path = JsonReader::read_string(obj, "texture", &mut error);
+        let slot_amount = JsonReader::read_u32(obj, "slots", &mut error) as usize;
+        let slot_capacity = JsonReader::read_u32(obj, "slot_capacity", &mut error) as usize;
+
+        if error {
+            log::error!(
+                "Failed to parse StorageChest from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("StorageChest succesfully loaded({})", name);
+        }
+
+        StorageChest {
+            name,
+            texture: AssetManager::get_asset_id(&tex_path),
+            facing: Direction::Up,
+            inventory: Inventory::new(slot_amount, slot_capacity),
+            output: None,
+            sent_item: None,
+        }
+    }
+
+    fn read_output(obj: &serde_json::Value, error: &mut bool) -> Option<Direction> {
+        match obj.get("output") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(_) => match Direction::from_name(&JsonReader::read_string(obj, "output", error)) {
+                Some(Direction::None) | None => {
+                    *error = true;
+                    None
+                }
+                output => output,
+            },
+        }
+    }
+
+    fn try_put_item(&mut self, item: &Item) -> bool {
+        if self.inventory.get_free_space(item.get_id()) == 0 {
+            return false;
+        }
+        self.inventory.try_put_items(item.clone(), 1);
+        true
+    }
+
+    // Pushed items don't take the room of the item sent out.
+    fn can_take_pushed(&self, item: &Item) -> bool {
+        let mut items = vec![(item.clone(), 1)];
+        if let Some(sent_item) = &self.sent_item {
+            items.push((sent_item.clone(), 1));
+        }
+        self.inventory.can_put_items(&items)
+    }
+}
+
+impl GameEntity for StorageChest {
+    fn update(&mut self, _parameters: &UpdateParameters) {}
+
+    fn tick(&mut self, _tick_id: u32) {}
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let sprite = Sprite::new(self.texture);
+        renderer.queue_render_sprite(sprite, transform);
+    }
+}
+
+impl WithGui for StorageChest {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        self.inventory.render_slots(params, CHEST_SLOT_PAYLOAD);
+        if params.ui.small_button("sort") {
+            self.inventory.sort();
+        }
+
+        params.ui.separator();
+        params.ui.text("output:");
+        let choices = [
+            None,
+            Some(Direction::Up),
+            Some(Direction::Right),
+            Some(Direction::Down),
+            Some(Direction::Left),
+        ];
+        for choice in choices {
+            let label = choice.map_or("none", |side| side.rotated_by(self.facing).get_name());
+            if imgui::Selectable::new(label)
+                .selected(self.output == choice)
+                .build(params.ui)
+            {
+                self.output = choice;
+            }
+        }
+    }
+}
+
+impl BuildingClone for StorageChest {
+    fn clone_box(&self) -> Box<dyn Building> {
+        Box::from(StorageChest {
+            name: self.name.clone(),
+            texture: self.texture,
+            facing: self.facing,
+            inventory: self.inventory.clone(),
+            output: self.output,
+            sent_item: self.sent_item.clone(),
+        })
+    }
+}
+
+impl Building for StorageChest {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        self.inventory.get_item_totals()
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+    }
+
+    // Output side relative to the facing, e.g. { "output": "up" }.
+    fn load_config(&mut self, config: &serde_json::Value, error: &mut bool) {
+        self.output = Self::read_output(config, error);
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        json!({
+            "inventory": self.inventory.save_state(),
+            "output": self.output.map(|side| side.get_name()),
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        match state.get("inventory") {
+            Some(inventory) => self.inventory.load_state(inventory, item_factory, error),
+            None => *error = true,
+        }
+        self.output = Self::read_output(state, error);
+    }
+
+    fn get_output_items(&self) -> Vec<(Item, usize)> {
+        self.inventory.get_item_totals()
+    }
+
+    fn take_output_items(&mut self, item_id: ItemId, amount: usize) -> usize {
+        self.inventory
+            .take_items(item_id, amount)
+            .map_or(0, |(_, taken)| taken)
+    }
+
+    fn put_input_items(&mut self, item: &Item, amount: usize) -> usize {
+        let amount = amount.min(self.inventory.get_free_space(item.get_id()));
+        self.inventory.try_put_items(item.clone(), amount);
+        amount
+    }
+}
+
+impl MessageSender for StorageChest {
+    // One item per tick from the first filled slot.
+    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        self.sent_item = None;
+        let output = match self.output {
+            Some(output) => output.rotated_by(self.facing),
+            None => return vec![],
+        };
+        let item = match self
+            .inventory
+            .get_first_filled_slot()
+            .and_then(|slot_id| self.inventory.take_from_slot(slot_id, 1))
+        {
+            Some((item, _)) => item,
+            None => return vec![],
+        };
+        self.sent_item = Some(item.clone());
+
+        vec![Message::FieldMessage(field_message::Message {
+            id: 0,
+            sender: field_message::MessageExchangeActor::default(),
+            receiver: field_message::MessageExchangeActor::default(),
+            target: field_message::Target::Directions(vec![output]),
+            tick_id,
+            refund: false,
+            ground_cell: None,
+            body: field_message::MessageBody::PushItem(TransportedItem::new(item)),
+        })]
+    }
+}
+
+impl MessageReceiver for StorageChest {
+    // Accepts from any side, refunds go back to the slots.
+    fn try_push_message(&mut self, message: Message) -> Option<Message> {
+        match &message {
+            Message::FieldMessage(msg) => match &msg.body {
+                field_message::MessageBody::PushItem(item) if msg.refund => {
+                    self.sent_item = None;
+                    if !self.try_put_item(item.get_item()) {
+                        log::error!("Chest {} can't take the refunded item back", self.name);
+                    }
+                    None
+                }
+                field_message::MessageBody::PushItem(item) => {
+                    if self.can_take_pushed(item.get_item()) && self.try_put_item(item.get_item()) {
+                        None
+                    } else {
+                        Some(message)
+                    }
+                }
+            },
+        }
+    }
+}
@@ -1,19 +1,25 @@
 use std::collections::HashMap;
 use std::iter;
 
-use super::item::*;
+use serde_json::json;
+
+use super::{item::*, item_sides::ItemSides};
 use crate::game::{
     common::{direction::Direction, json_reader::JsonReader, math::Vec2},
     game_entity::GameEntity,
     gui::with_gui::*,
-    location::field::building::{Building, BuildingClone},
+    location::field::building::{BeltBuilding, Building, BuildingClone},
     message::*,
     Renderer, SpriteTransform, UpdateParameters,
 };
 
 pub struct TransportBelt {
     name: String,
+    facing: Direction,
+    // Sides of a newly built belt, the only output side is the belt's direction.
+    item_sides: ItemSides,
 
+    // Absolute directions, i.e. already rotated by the facing.
     inputs: Vec<Direction>,
     output: Direction,
     // For input buffers 0th element in vec is the edge cell
@@ -33,6 +39,10 @@ impl TransportBelt {
 
         let name = JsonReader::read_string(obj, "name", &mut error);
         let item_count = JsonReader::read_i32(obj, "item_count", &mut error) as u32;
+        let item_sides = ItemSides::from_json_object(obj, &mut error);
+        if item_sides.get_output_directions(Direction::Up).len() != 1 {
+            error = true;
+        }
 
         if error {
             log::error!(
@@ -47,19 +57,61 @@ impl TransportBelt {
             log::info!("TransportBelt succesfully loaded({})", name);
         }
 
-        TransportBelt {
+        let mut belt = TransportBelt {
             name,
+            facing: Direction::Up,
+            item_sides,
             inputs: Vec::new(),
             output: Direction::None,
             item_buffers: HashMap::new(),
             item_count,
-        }
+        };
+        belt.set_default_config();
+        belt
+    }
+
+    fn set_default_config(&mut self) {
+        let inputs = self.item_sides.get_input_directions(self.facing);
+        let output = self
+            .item_sides
+            .get_output_directions(self.facing)
+            .first()
+            .copied()
+            .unwrap_or(Direction::None);
+        self.set_config(inputs, output);
+    }
+
+    // Reads "inputs" and "output" directions by their names.
+    pub fn config_from_json_object(
+        obj: &serde_json::Value,
+        error: &mut bool,
+    ) -> (Vec<Direction>, Direction) {
+        let input_names: Vec<String> = JsonReader::read_vec(obj, "inputs", error)
+            .iter()
+            .map(|dir| String::from(dir.as_str().unwrap_or("")))
+            .collect();
+        let output_name = JsonReader::read_string(obj, "output", error);
+
+        let mut read_direction = |name: &str| {
+            Direction::from_name(name).unwrap_or_else(|| {
+                *error = true;
+                Direction::None
+            })
+        };
+        let inputs = input_names
+            .iter()
+            .map(|name| read_direction(name))
+            .collect();
+        let output = read_direction(&output_name);
+
+        (inputs, output)
     }
 
     pub fn set_config(&mut self, inputs: Vec<Direction>, output: Direction) {
         self.inputs = inputs;
         self.output = output;
 
+        self.item_buffers.clear();
         for &dir in self.inputs.iter().chain(iter::once(&self.output)) {
             let mut buffer = Vec::with_capacity(self.item_count as usize);
             for _ in 0..self.item_count {
@@ -233,18 +285,40 @@ impl GameEntity for TransportBelt {
 }
 
 impl WithGui for TransportBelt {
-    fn render_gui(&mut self, params: &mut GuiRenderParams) {}
+    // Slots of each side from the edge to the output edge.
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        for dir in self.inputs.iter().chain(iter::once(&self.output)) {
+            let buffer = self.item_buffers.get(dir).unwrap();
+            let slots: Vec<&str> = buffer
+                .iter()
+                .map(|slot| match slot {
+                    Some(item) => item.get_item().get_name(),
+                    None => "-",
+                })
+                .collect();
+            params.ui.text(format!(
+                "{} {}: {}",
+                if *dir == self.output { "out" } else { "in" },
+                dir.get_name(),
+                slots.join(", ")
+            ));
+        }
+    }
 }
 
 impl BuildingClone for TransportBelt {
     fn clone_box(&self) -> Box<dyn Building> {
-        Box::from(TransportBelt {
+        let mut belt = TransportBelt {
             name: self.name.clone(),
+            facing: self.facing,
+            item_sides: self.item_sides.clone(),
             inputs: Vec::new(),
             output: Direction::None,
             item_buffers: HashMap::new(),
             item_count: self.item_count,
-        })
+        };
+        belt.set_default_config();
+        Box::from(belt)
     }
 }
 
@@ -252,6 +326,145 @@ impl Building for TransportBelt {
     fn get_name(&self) -> &str {
         &self.name
     }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        stack_transported_items(self.item_buffers.values().flatten().flatten())
+    }
+
+    fn as_belt(&self) -> Option<&dyn BeltBuilding> {
+        Some(self)
+    }
+
+    fn as_belt_mut(&mut self) -> Option<&mut dyn BeltBuilding> {
+        Some(self)
+    }
+
+    // Items keep their places relatively to the belt.
+    fn set_facing(&mut self, facing: Direction) {
+        let old_facing = self.facing;
+        let rotate = |dir: Direction| dir.unrotated_by(old_facing).rotated_by(facing);
+
+        self.facing = facing;
+        self.inputs = self.inputs.iter().map(|&dir| rotate(dir)).collect();
+        self.output = rotate(self.output);
+        self.item_buffers = std::mem::take(&mut self.item_buffers)
+            .into_iter()
+            .map(|(dir, buffer)| (rotate(dir), buffer))
+            .collect();
+
+        for dir in self
+            .inputs
+            .clone()
+            .into_iter()
+            .chain(iter::once(self.output))
+        {
+            for i in 0..self.item_count as usize {
+                let position = self.compute_item_position(dir, i as i32);
+                if let Some(item) = self.item_buffers.get_mut(&dir).unwrap()[i].as_mut() {
+                    item.set_movement(position, position, item.last_tick_moved);
+                }
+            }
+        }
+    }
+
+    // Config directions are relative to the facing.
+    fn load_config(&mut self, config: &serde_json::Value, error: &mut bool) {
+        let (inputs, output) = Self::config_from_json_object(config, error);
+        self.set_config(
+            inputs
+                .into_iter()
+                .map(|dir| dir.rotated_by(self.facing))
+                .collect(),
+            output.rotated_by(self.facing),
+        );
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        let mut item_buffers = serde_json::Map::new();
+        for dir in self.inputs.iter().chain(iter::once(&self.output)) {
+            let buffer = self.item_buffers.get(dir).unwrap();
+            let items = buffer
+                .iter()
+                .map(|item| match item {
+                    Some(item) => item.save_state(),
+                    None => serde_json::Value::Null,
+                })
+                .collect();
+            item_buffers.insert(
+                String::from(dir.get_name()),
+                serde_json::Value::Array(items),
+            );
+        }
+
+        json!({
+            "inputs": self.inputs.iter().map(|dir| dir.get_name()).collect::<Vec<_>>(),
+            "output": self.output.get_name(),
+            "item_buffers": item_buffers,
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        let (inputs, output) = Self::config_from_json_object(state, error);
+        self.set_config(inputs, output);
+
+        let item_buffers = JsonReader::read_obj(state, "item_buffers", error);
+        for dir in self.inputs.clone().iter().chain(iter::once(&self.output)) {
+            let saved_items = JsonReader::read_vec(&item_buffers, dir.get_name(), error);
+            let buffer = self.item_buffers.get_mut(dir).unwrap();
+            if saved_items.len() != buffer.len() {
+                *error = true;
+                continue;
+            }
+
+            for (slot, saved_item) in buffer.iter_mut().zip(saved_items.iter()) {
+                *slot = match saved_item {
+                    serde_json::Value::Null => None,
+                    saved_item => Some(TransportedItem::from_saved_state(
+                        saved_item,
+                        item_factory,
+                        error,
+                    )),
+                };
+            }
+        }
+    }
+}
+
+impl BeltBuilding for TransportBelt {
+    // Input sides first, then the output, each from its first slot.
+    fn get_slot_items(&self) -> Vec<Item> {
+        self.inputs
+            .iter()
+            .chain(iter::once(&self.output))
+            .flat_map(|dir| self.item_buffers[dir].iter().flatten())
+            .map(|item| item.get_item().clone())
+            .collect()
+    }
+
+    fn take_slot_item(&mut self, item_id: ItemId) -> bool {
+        for dir in self.inputs.iter().chain(iter::once(&self.output)) {
+            let slot = self
+                .item_buffers
+                .get_mut(dir)
+                .unwrap()
+                .iter_mut()
+                .find(|slot| matches!(slot, Some(item) if item.get_id() == item_id));
+            if let Some(slot) = slot {
+                *slot = None;
+                return true;
+            }
+        }
+        false
+    }
 }
 
 impl MessageSender for TransportBelt {
@@ -267,6 +480,7 @@ impl MessageSender for TransportBelt {
                     target: field_message::Target::Directions(vec![self.output]),
                     tick_id,
                     refund: false,
+                    ground_cell: None,
                     body: field_message::MessageBody::PushItem(item),
                 })]
             }
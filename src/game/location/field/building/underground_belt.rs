@@ -0,0 +1,323 @@
+use serde_json::json;
+
+use super::item::*;
+use crate::game::{
+    common::{
+        direction::Direction,
+        json_reader::JsonReader,
+        math::{IVec2, Vec2},
+    },
+    game_entity::GameEntity,
+    gui::with_gui::*,
+    location::field::building::{BeltBuilding, Building, BuildingClone, TunnelBuilding},
+    message::*,
+    Renderer, SpriteTransform, UpdateParameters,
+};
+
+#[derive(Clone)]
+enum TunnelEnd {
+    Entrance {
+        // Max distance(in cells) to the exit ahead.
+        range: u32,
+        // Linked by the field every tick.
+        exit: Option<IVec2>,
+    },
+    Exit,
+}
+
+// Entrance takes the items from its back and passes them under the ground
+// straight to the exit facing the same way, which pushes them out of its front.
+pub struct UndergroundBelt {
+    name: String,
+    facing: Direction,
+    end: TunnelEnd,
+    slot: Option<TransportedItem>,
+}
+
+impl UndergroundBelt {
+    pub fn from_json_object(obj: &serde_json::Value) -> UndergroundBelt {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let end = match JsonReader::read_string(obj, "type", &mut error).as_str() {
+            "underground entrance" => TunnelEnd::Entrance {
+                range: JsonReader::read_u32(obj, "range", &mut error),
+                exit: None,
+            },
+            "underground exit" => TunnelEnd::Exit,
+            _ => {
+                error = true;
+                TunnelEnd::Exit
+            }
+        };
+
+        if error {
+            log::error!(
+                "Failed to parse UndergroundBelt from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("UndergroundBelt succesfully loaded({})", name);
+        }
+
+        UndergroundBelt {
+            name,
+            facing: Direction::Up,
+            end,
+            slot: None,
+        }
+    }
+
+    fn get_back(&self) -> Direction {
+        Direction::Down.rotated_by(self.facing)
+    }
+
+    fn get_edge_position(direction: Direction) -> Vec2 {
+        direction.to_ivec2().to_vec2() * 0.5
+    }
+
+    fn try_push_item(
+        &mut self,
+        mut item: TransportedItem,
+        msg: &field_message::Message,
+    ) -> Option<TransportedItem> {
+        if self.slot.is_some() || item.last_tick_moved == msg.tick_id {
+            return Some(item);
+        }
+
+        // Entrance is fed by the belts behind, exit only from the tunnel.
+        let from = match (&self.end, &msg.target) {
+            (TunnelEnd::Entrance { .. }, field_message::Target::Directions(_))
+                if Direction::from_ivec2(
+                    msg.sender.get_position() - msg.receiver.get_position(),
+                ) == self.get_back() =>
+            {
+                Self::get_edge_position(self.get_back())
+            }
+            (TunnelEnd::Exit, field_message::Target::Position(_)) => Vec2::zero(),
+            _ => return Some(item),
+        };
+
+        item.set_movement(from, Vec2::zero(), msg.tick_id);
+        item.last_tick_moved = msg.tick_id;
+        self.slot = Some(item);
+        None
+    }
+
+    fn pull_item_failed(&mut self, mut item: TransportedItem, tick_id: u32) {
+        item.set_movement(Vec2::zero(), Vec2::zero(), tick_id);
+        self.slot = Some(item);
+    }
+}
+
+impl GameEntity for UndergroundBelt {
+    fn update(&mut self, parameters: &UpdateParameters) {
+        if let Some(item) = self.slot.as_mut() {
+            item.update(parameters);
+        }
+    }
+
+    fn tick(&mut self, _tick_id: u32) {}
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        if let Some(item) = self.slot.as_mut() {
+            item.render(renderer, transform);
+        }
+    }
+}
+
+impl WithGui for UndergroundBelt {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        match &self.end {
+            TunnelEnd::Entrance { range, exit } => match exit {
+                Some(exit) => params.ui.text(format!("exit: {}", exit)),
+                None => params.ui.text(format!("no exit within {} cells", range)),
+            },
+            TunnelEnd::Exit => params.ui.text("exit"),
+        }
+        params.ui.text(format!(
+            "item: {}",
+            self.slot
+                .as_ref()
+                .map_or("-", |item| item.get_item().get_name())
+        ));
+    }
+}
+
+impl BuildingClone for UndergroundBelt {
+    fn clone_box(&self) -> Box<dyn Building> {
+        Box::from(UndergroundBelt {
+            name: self.name.clone(),
+            facing: self.facing,
+            end: self.end.clone(),
+            slot: None,
+        })
+    }
+}
+
+impl Building for UndergroundBelt {
+    fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn get_facing(&self) -> Direction {
+        self.facing
+    }
+
+    fn get_contents(&self) -> Vec<(Item, usize)> {
+        stack_transported_items(self.slot.iter())
+    }
+
+    fn set_facing(&mut self, facing: Direction) {
+        self.facing = facing;
+        if let Some(item) = self.slot.as_mut() {
+            item.set_movement(Vec2::zero(), Vec2::zero(), item.last_tick_moved);
+        }
+    }
+
+    fn as_belt(&self) -> Option<&dyn BeltBuilding> {
+        Some(self)
+    }
+
+    fn as_belt_mut(&mut self) -> Option<&mut dyn BeltBuilding> {
+        Some(self)
+    }
+
+    fn as_tunnel(&self) -> Option<&dyn TunnelBuilding> {
+        Some(self)
+    }
+
+    fn as_tunnel_mut(&mut self) -> Option<&mut dyn TunnelBuilding> {
+        Some(self)
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        json!({
+            "slot": match &self.slot {
+                Some(item) => item.save_state(),
+                None => serde_json::Value::Null,
+            },
+        })
+    }
+
+    fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        self.slot = match state.get("slot") {
+            None => {
+                *error = true;
+                None
+            }
+            Some(serde_json::Value::Null) => None,
+            Some(saved_item) => Some(TransportedItem::from_saved_state(
+                saved_item,
+                item_factory,
+                error,
+            )),
+        };
+    }
+}
+
+impl BeltBuilding for UndergroundBelt {
+    fn get_slot_items(&self) -> Vec<Item> {
+        self.slot
+            .iter()
+            .map(|item| item.get_item().clone())
+            .collect()
+    }
+
+    fn take_slot_item(&mut self, item_id: ItemId) -> bool {
+        match &self.slot {
+            Some(item) if item.get_id() == item_id => {
+                self.slot = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl TunnelBuilding for UndergroundBelt {
+    fn get_tunnel_range(&self) -> Option<u32> {
+        match self.end {
+            TunnelEnd::Entrance { range, .. } => Some(range),
+            TunnelEnd::Exit => None,
+        }
+    }
+
+    fn is_tunnel_exit(&self) -> bool {
+        matches!(self.end, TunnelEnd::Exit)
+    }
+
+    fn set_tunnel_exit(&mut self, position: Option<IVec2>) {
+        if let TunnelEnd::Entrance { exit, .. } = &mut self.end {
+            *exit = position;
+        }
+    }
+}
+
+impl MessageSender for UndergroundBelt {
+    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        let target = match self.end {
+            TunnelEnd::Entrance {
+                exit: Some(exit), ..
+            } => field_message::Target::Position(exit),
+            // Waits until an exit is built.
+            TunnelEnd::Entrance { exit: None, .. } => return vec![],
+            TunnelEnd::Exit => field_message::Target::Directions(vec![self.facing]),
+        };
+
+        let mut item = match self.slot.take() {
+            Some(item) if item.last_tick_moved != tick_id => item,
+            item => {
+                self.slot = item;
+                return vec![];
+            }
+        };
+        if let field_message::Target::Directions(_) = target {
+            item.set_movement(Vec2::zero(), Self::get_edge_position(self.facing), tick_id);
+        }
+
+        vec![Message::FieldMessage(field_message::Message {
+            id: 0,
+            sender: field_message::MessageExchangeActor::default(),
+            receiver: field_message::MessageExchangeActor::default(),
+            target,
+            tick_id,
+            refund: false,
+            ground_cell: None,
+            body: field_message::MessageBody::PushItem(item),
+        })]
+    }
+}
+
+impl MessageReceiver for UndergroundBelt {
+    fn try_push_message(&mut self, mut message: Message) -> Option<Message> {
+        match message {
+            Message::FieldMessage(ref mut msg) => match &msg.body {
+                field_message::MessageBody::PushItem(item) => {
+                    let item = item.clone();
+                    if msg.refund {
+                        self.pull_item_failed(item, msg.tick_id);
+                        return None;
+                    }
+
+                    match self.try_push_item(item, msg) {
+                        Some(item) => {
+                            msg.body = field_message::MessageBody::PushItem(item);
+                            Some(message)
+                        }
+                        None => None,
+                    }
+                }
+            },
+        }
+    }
+}
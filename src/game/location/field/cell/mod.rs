@@ -1,50 +1,168 @@
-use crate::game::common::math::Vec2;
+use serde_json::json;
+
+use crate::game::common::{
+    direction::Direction,
+    json_reader::JsonReader,
+    math::{IVec2, Vec2},
+};
 use crate::game::game_entity::*;
 use crate::game::gui::with_gui::*;
 use crate::game::renderer::Renderer;
 
 use crate::game::{
     location::{
-        field::building::Building,
+        field::building::{footprint_transform, Building},
         physics_scene::{
             message as physics_message, Body, BodyCollection, BodyHierarchyRoot, Collider,
             ColliderShape, PhysicsSimulated,
         },
+        save::LoadContext,
     },
     message::*,
 };
 
 pub mod surface;
-use surface::Surface;
+use surface::{Surface, SurfaceFactory};
 
 #[derive(Default)]
 pub struct Cell {
     surface: Surface,
+    // Amount of ore left in the cell, None if it's unlimited.
+    deposit: Option<u32>,
     building: Option<Box<dyn Building>>,
+    // Origin cell of the multi-cell building which occupies this one.
+    building_origin: Option<IVec2>,
     body: Option<Body>,
 }
 
 impl Cell {
     pub fn new(surface: Surface) -> Cell {
+        Self::with_deposit(surface, None)
+    }
+
+    pub fn with_deposit(surface: Surface, deposit: Option<u32>) -> Cell {
         Cell {
             surface,
+            deposit,
             building: None,
+            building_origin: None,
             body: None,
         }
     }
 
-    pub fn build(&mut self, building: Box<dyn Building>, center: Vec2) {
+    pub fn get_surface(&self) -> &Surface {
+        &self.surface
+    }
+
+    // Building may be mining the deposit right now.
+    pub fn get_deposit(&self) -> Option<u32> {
+        self.building
+            .as_ref()
+            .and_then(|building| building.get_deposit())
+            .or(self.deposit)
+    }
+
+    pub fn get_building(&self) -> Option<&dyn Building> {
+        self.building.as_deref()
+    }
+
+    pub fn get_building_mut(&mut self) -> Option<&mut (dyn Building + 'static)> {
+        self.building.as_deref_mut()
+    }
+
+    pub fn get_building_origin(&self) -> Option<IVec2> {
+        self.building_origin
+    }
+
+    pub fn set_building_origin(&mut self, origin: Option<IVec2>) {
+        self.building_origin = origin;
+    }
+
+    // Either has a building or is covered by a multi-cell one.
+    pub fn is_occupied(&self) -> bool {
+        self.building.is_some() || self.building_origin.is_some()
+    }
+
+    // Fails if the building can't be placed on this cell.
+    // Center is the center of the whole building footprint.
+    pub fn build(&mut self, mut building: Box<dyn Building>, center: Vec2) -> Result<(), String> {
+        building.on_build(&self.surface, self.deposit)?;
         self.building = Some(building);
+        self.update_body(center);
+
+        Ok(())
+    }
 
-        self.body = Some(Body::new_static(
-            Collider::new(
-                ColliderShape::Box {
-                    size: Vec2::new(1.0, 1.0),
-                },
-                Vec2::zero(),
-            ),
-            center,
-        ));
+    pub fn take_building(&mut self) -> Option<Box<dyn Building>> {
+        self.body = None;
+        self.building.take()
+    }
+
+    // Footprint of the building changes with its facing.
+    pub fn rotate_building(&mut self, facing: Direction, center: Vec2) {
+        if let Some(building) = self.building.as_mut() {
+            building.set_facing(facing);
+            self.update_body(center);
+        }
+    }
+
+    fn update_body(&mut self, center: Vec2) {
+        self.body = self.building.as_ref().map(|building| {
+            Body::new_static(
+                Collider::new(
+                    ColliderShape::Box {
+                        size: building.get_size().to_vec2(),
+                    },
+                    Vec2::zero(),
+                ),
+                center,
+            )
+        });
+    }
+
+    pub fn render_surface(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        self.surface.render(renderer, transform);
+    }
+
+    pub fn render_building(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        if let Some(building) = self.building.as_mut() {
+            let footprint_transform = footprint_transform(building.get_size());
+            building.render(renderer, transform.combine(&footprint_transform));
+        }
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        let building = match &self.building {
+            Some(building) => json!({
+                "name": building.get_name(),
+                "facing": building.get_facing().get_name(),
+                "state": building.save_state(),
+            }),
+            None => serde_json::Value::Null,
+        };
+
+        json!({
+            "surface": self.surface.get_name(),
+            "deposit": self.get_deposit(),
+            "building": building,
+        })
+    }
+
+    // Building is restored separately, as it may occupy several cells.
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        context: &LoadContext,
+        error: &mut bool,
+    ) -> Cell {
+        let surface_name = JsonReader::read_string(state, "surface", error);
+        let surface = context
+            .surface_factory
+            .create_surface(SurfaceFactory::get_surface_id_by_name(&surface_name));
+        let deposit = match state.get("deposit") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(_) => Some(JsonReader::read_u32(state, "deposit", error)),
+        };
+        Cell::with_deposit(surface, deposit)
     }
 }
 
@@ -62,10 +180,8 @@ impl GameEntity for Cell {
     }
 
     fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
-        self.surface.render(renderer, transform.clone());
-        if let Some(building) = self.building.as_mut() {
-            building.render(renderer, transform);
-        }
+        self.render_surface(renderer, transform.clone());
+        self.render_building(renderer, transform);
     }
 }
 
@@ -11,6 +11,7 @@ pub use surface_factory::*;
 #[derive(Clone)]
 pub struct Surface {
     id: SurfaceId,
+    name: String,
     sprite: Sprite,
 }
 
@@ -18,6 +19,7 @@ impl Default for Surface {
     fn default() -> Self {
         Surface {
             id: SurfaceId(0),
+            name: String::new(),
             sprite: Sprite::new(AssetId::null()),
         }
     }
@@ -27,11 +29,20 @@ impl Default for Surface {
 pub struct SurfaceId(u64);
 
 impl Surface {
+    pub fn get_id(&self) -> SurfaceId {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
     fn new_error() -> Surface {
         let texture = AssetManager::get_asset_id("error_fallbacks/texture.png");
         let sprite = Sprite::new(texture);
         Surface {
             id: SurfaceId(0),
+            name: String::from("error"),
             sprite,
         }
     }
@@ -60,6 +71,7 @@ impl Surface {
                 let sprite = Sprite::new(AssetManager::get_asset_id(tex_path.as_str()));
                 Surface {
                     id: SurfaceFactory::get_surface_id_by_name(name),
+                    name: name.clone(),
                     sprite,
                 }
             }
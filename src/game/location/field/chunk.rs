@@ -0,0 +1,132 @@
+use crate::game::{
+    common::math::{IVec2, Vec2},
+    location::physics_scene::{
+        message as physics_message, BodyCollection, BodyHierarchyRoot, PhysicsSimulated,
+    },
+};
+
+use super::{building::Building, cell::Cell, world_generator::WorldGenerator};
+
+// Chunk side in cells.
+pub const CHUNK_SIZE: isize = 32;
+
+// Square of cells that is loaded as a whole.
+pub struct Chunk {
+    // Coords of the chunk, not of the cell.
+    position: IVec2,
+    cells: Vec<Cell>,
+    // Buildings with origin in the chunk, kept by build and take_building.
+    building_count: usize,
+}
+
+impl Chunk {
+    pub fn new(position: IVec2, generator: &WorldGenerator) -> Chunk {
+        let cell_count = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+        let origin = position * CHUNK_SIZE;
+        let mut cells = Vec::with_capacity(cell_count);
+        for id in 0..cell_count {
+            cells.push(generator.generate_cell(origin + Self::get_local_position(id)));
+        }
+
+        Chunk {
+            position,
+            cells,
+            building_count: 0,
+        }
+    }
+
+    pub fn get_chunk_position(cell_position: IVec2) -> IVec2 {
+        IVec2::new(
+            cell_position.x.div_euclid(CHUNK_SIZE),
+            cell_position.y.div_euclid(CHUNK_SIZE),
+        )
+    }
+
+    pub fn get_position(&self) -> IVec2 {
+        self.position
+    }
+
+    pub fn get_cell(&self, cell_position: IVec2) -> &Cell {
+        &self.cells[Self::get_cell_index(cell_position)]
+    }
+
+    pub fn get_cell_mut(&mut self, cell_position: IVec2) -> &mut Cell {
+        &mut self.cells[Self::get_cell_index(cell_position)]
+    }
+
+    // Buildings must be added and removed through the chunk to keep it active.
+    pub fn build(
+        &mut self,
+        cell_position: IVec2,
+        building: Box<dyn Building>,
+        center: Vec2,
+    ) -> Result<(), String> {
+        self.get_cell_mut(cell_position).build(building, center)?;
+        self.building_count += 1;
+        Ok(())
+    }
+
+    pub fn take_building(&mut self, cell_position: IVec2) -> Option<Box<dyn Building>> {
+        let building = self.get_cell_mut(cell_position).take_building();
+        if building.is_some() {
+            self.building_count -= 1;
+        }
+        building
+    }
+
+    // Chunks without buildings have nothing to tick.
+    pub fn is_active(&self) -> bool {
+        self.building_count > 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (IVec2, &Cell)> {
+        let origin = self.position * CHUNK_SIZE;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(id, cell)| (origin + Self::get_local_position(id), cell))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (IVec2, &mut Cell)> {
+        let origin = self.position * CHUNK_SIZE;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(id, cell)| (origin + Self::get_local_position(id), cell))
+    }
+
+    // Cells are stored column by column.
+    fn get_cell_index(cell_position: IVec2) -> usize {
+        let x = cell_position.x.rem_euclid(CHUNK_SIZE);
+        let y = cell_position.y.rem_euclid(CHUNK_SIZE);
+        (x * CHUNK_SIZE + y) as usize
+    }
+
+    fn get_local_position(id: usize) -> IVec2 {
+        IVec2::new(id as isize / CHUNK_SIZE, id as isize % CHUNK_SIZE)
+    }
+}
+
+impl PhysicsSimulated for Chunk {
+    fn get_bodies(&mut self) -> BodyHierarchyRoot {
+        BodyHierarchyRoot::new(
+            self.cells
+                .iter_mut()
+                .map(|cell| cell.get_bodies())
+                .collect(),
+            BodyCollection::default(),
+        )
+    }
+
+    fn handle_physics_messages(&mut self, messages: physics_message::MessageHierarchy) {
+        for (cell, cell_messages) in self.cells.iter_mut().zip(messages.nested.into_iter()) {
+            cell.handle_physics_messages(cell_messages);
+        }
+    }
+
+    fn physics_update(&mut self, delta_time: f32) {
+        self.cells
+            .iter_mut()
+            .for_each(|cell| cell.physics_update(delta_time));
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use serde_json::json;
+
+use crate::game::common::json_reader::JsonReader;
+
+#[derive(PartialEq, Eq, Copy, Clone, Hash)]
+pub struct FluidId(u64);
+
+#[derive(Clone)]
+pub struct Fluid {
+    id: FluidId,
+    name: String,
+}
+
+impl Fluid {
+    pub fn new(name: &str) -> Fluid {
+        Fluid {
+            id: FluidFactory::get_fluid_id_by_name(name),
+            name: String::from(name),
+        }
+    }
+
+    pub fn get_id(&self) -> FluidId {
+        self.id
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+}
+
+pub struct FluidFactory {
+    // Sorted by name.
+    fluids: Vec<Fluid>,
+}
+
+impl FluidFactory {
+    pub fn new(json: Rc<str>) -> FluidFactory {
+        let fluids_arr = serde_json::from_str(json.as_ref()).unwrap_or_else(|e| {
+            log::error!("Fluid dictionary haven't been succesfully loaded : {}", e);
+            serde_json::Value::Array(Vec::new())
+        });
+
+        let mut fluids = vec![];
+        match fluids_arr {
+            serde_json::Value::Array(arr) => {
+                for obj in arr {
+                    let mut error = false;
+                    let name = JsonReader::read_string(&obj, "name", &mut error);
+                    if error {
+                        log::error!("Fluid dictionary haven't been succesfully loaded : wrong JSON file structure(wrong fluid format)");
+                        continue;
+                    }
+                    fluids.push(Fluid::new(&name));
+                }
+            }
+            _ => {
+                log::error!("Fluid dictionary haven't been succesfully loaded : wrong JSON file structure(the top-level must be an array)");
+            }
+        }
+        fluids.sort_by(|a, b| a.name.cmp(&b.name));
+
+        log::info!("{} fluids are loaded", fluids.len());
+        FluidFactory { fluids }
+    }
+
+    pub fn get_sorted_fluids(&self) -> &[Fluid] {
+        &self.fluids
+    }
+
+    pub fn get_fluid_id_by_name(name: &str) -> FluidId {
+        let mut hasher = DefaultHasher::new();
+        name.hash(&mut hasher);
+        FluidId(hasher.finish())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FluidPortMode {
+    In,
+    Out,
+    // Pipes and tanks, the fluid goes both ways.
+    Both,
+}
+
+// Volume of one fluid held by a building.
+#[derive(Clone)]
+pub struct FluidTank {
+    pub fluid: Fluid,
+    pub mode: FluidPortMode,
+    pub capacity: u32,
+    pub volume: u32,
+}
+
+impl FluidTank {
+    pub fn new(fluid: Fluid, mode: FluidPortMode, capacity: u32) -> FluidTank {
+        FluidTank {
+            fluid,
+            mode,
+            capacity,
+            volume: 0,
+        }
+    }
+
+    pub fn get_free_space(&self) -> u32 {
+        self.capacity.saturating_sub(self.volume)
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.volume >= self.capacity
+    }
+
+    // Pipes and tanks even out their fill levels, one-way ports move as much as fits.
+    pub fn get_flow(&self, to: &FluidTank) -> u32 {
+        if self.fluid.get_id() != to.fluid.get_id()
+            || self.mode == FluidPortMode::In
+            || to.mode == FluidPortMode::Out
+        {
+            return 0;
+        }
+
+        let amount = if self.mode == FluidPortMode::Both && to.mode == FluidPortMode::Both {
+            let (from_volume, from_capacity) = (self.volume as u64, self.capacity as u64);
+            let (to_volume, to_capacity) = (to.volume as u64, to.capacity as u64);
+            ((from_volume * to_capacity).saturating_sub(to_volume * from_capacity)
+                / (from_capacity + to_capacity).max(1)) as u32
+        } else {
+            self.volume
+        };
+        amount.min(to.get_free_space())
+    }
+
+    pub fn save_tanks(tanks: &[FluidTank]) -> serde_json::Value {
+        serde_json::Value::Array(
+            tanks
+                .iter()
+                .map(|tank| json!({ "fluid": tank.fluid.get_name(), "volume": tank.volume }))
+                .collect(),
+        )
+    }
+
+    // Static data of the tanks comes from the dictionary, only volumes are restored.
+    pub fn load_tanks(
+        tanks: &mut [FluidTank],
+        state: &serde_json::Value,
+        name: &str,
+        error: &mut bool,
+    ) {
+        for saved_tank in JsonReader::read_vec(state, name, error) {
+            let fluid_name = JsonReader::read_string(&saved_tank, "fluid", error);
+            let volume = JsonReader::read_u32(&saved_tank, "volume", error);
+            match tanks
+                .iter_mut()
+                .find(|tank| tank.fluid.get_name() == fluid_name)
+            {
+                Some(tank) => tank.volume = volume.min(tank.capacity),
+                None => *error = true,
+            }
+        }
+    }
+}
+
+// Cuts the amounts down proportionally to fit into the total, the remainder goes to the first ones.
+pub fn share(total: u32, amounts: &mut [u32]) {
+    let sum: u64 = amounts.iter().map(|&amount| amount as u64).sum();
+    if sum <= total as u64 {
+        return;
+    }
+
+    let wanted = amounts.to_vec();
+    let mut left = total;
+    for amount in amounts.iter_mut() {
+        *amount = (*amount as u64 * total as u64 / sum) as u32;
+        left -= *amount;
+    }
+    for (amount, wanted) in amounts.iter_mut().zip(wanted) {
+        if left == 0 {
+            break;
+        }
+        if *amount < wanted {
+            *amount += 1;
+            left -= 1;
+        }
+    }
+}
@@ -1,28 +1,153 @@
-use crate::game::common::asset_manager::AssetManager;
-use crate::game::common::math::Vec2;
+use serde_json::json;
+
+use crate::game::common::{
+    json_reader::JsonReader,
+    json_writer::JsonWriter,
+    math::{IVec2, Vec2},
+};
 use crate::game::game_entity::*;
-use crate::game::location::physics_scene::{message::MessageHierarchy, BodyCollection, *};
+use crate::game::location::{
+    field::building::item::{Item, ItemFactory},
+    physics_scene::{
+        message::{MessageBody, MessageHierarchy},
+        BodyCollection, *,
+    },
+    player::inventory::Inventory,
+};
 use crate::game::renderer::Sprite;
 
+pub const SIZE: f32 = 0.5;
+// Max amount of items in a stack lying in one cell.
+pub const STACK_CAPACITY: usize = 16;
+const MASS: f32 = 1.0;
+const FRICTION: f32 = 0.99;
+
+// Stack of items lying on the ground, picked up by the player on contact.
 pub struct LayingObject {
     sprite: Sprite,
     pub body: Body,
+    item: Item,
+    amount: usize,
+    // Stack dropped by the player isn't picked up until the player steps off it.
+    pickup_blocked: bool,
+    // Bodies entered the trigger in the last physics step.
+    touching: Vec<BodyId>,
 }
 
 impl LayingObject {
-    pub fn new(position: Vec2, mass: f32) -> LayingObject {
-        let tex = AssetManager::get_asset_id("textures/13.png");
-        let sprite = Sprite::new(tex);
+    pub fn new(position: Vec2, item: Item, amount: usize) -> LayingObject {
+        let sprite = Sprite::new(item.get_sprite_asset_id());
 
-        let collider = Collider::new(
+        let collider = Collider::new_trigger(
             ColliderShape::Box {
-                size: Vec2::new_xy(0.5),
+                size: Vec2::new_xy(SIZE),
             },
             Vec2::zero(),
         );
-        let body = Body::new_dynamic(collider, mass, position, 0.99);
+        let body = Body::new_dynamic(collider, MASS, position, FRICTION);
+
+        LayingObject {
+            sprite,
+            body,
+            item,
+            amount,
+            pickup_blocked: false,
+            touching: vec![],
+        }
+    }
+
+    pub fn get_item(&self) -> &Item {
+        &self.item
+    }
+
+    pub fn get_amount(&self) -> usize {
+        self.amount
+    }
+
+    pub fn get_cell_position(&self) -> IVec2 {
+        self.body.get_position().to_ivec2_rounded()
+    }
+
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        let offset = point - self.body.get_position();
+        offset.x.abs() <= SIZE * 0.5 && offset.y.abs() <= SIZE * 0.5
+    }
+
+    // Returns the amount added, other items can't be mixed into the stack.
+    pub fn add_items(&mut self, item: &Item, amount: usize) -> usize {
+        if item.get_id() != self.item.get_id() {
+            return 0;
+        }
+        let added = amount.min(STACK_CAPACITY - self.amount);
+        self.amount += added;
+        added
+    }
+
+    pub fn block_pickup(&mut self) {
+        self.pickup_blocked = true;
+    }
+
+    // Moves as much of the stack as fits into the inventory of the touching picker.
+    pub fn try_pick_up(&mut self, picker: BodyId, inventory: &mut Inventory) {
+        if !self.touching.contains(&picker) {
+            self.pickup_blocked = false;
+            return;
+        }
+        if self.pickup_blocked {
+            return;
+        }
 
-        LayingObject { sprite, body }
+        let amount = self
+            .amount
+            .min(inventory.get_free_space(self.item.get_id()));
+        if amount > 0 {
+            inventory.try_put_items(self.item.clone(), amount);
+            self.amount -= amount;
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        json!({
+            "position": JsonWriter::write_vec2(self.body.get_position()),
+            "velocity": JsonWriter::write_vec2(self.body.get_velocity()),
+            "item": self.item.get_name(),
+            "amount": self.amount,
+            "pickup_blocked": self.pickup_blocked,
+        })
+    }
+
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) -> LayingObject {
+        let mut laying_object = Self::from_json_object(state, item_factory, error);
+        laying_object
+            .body
+            .set_velocity(JsonReader::read_vec2(state, "velocity", error));
+        laying_object.pickup_blocked = JsonReader::read_bool(state, "pickup_blocked", error);
+        laying_object
+    }
+
+    // Position and the stack, e.g. from a scenario file.
+    pub fn from_json_object(
+        obj: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) -> LayingObject {
+        let position = JsonReader::read_vec2(obj, "position", error);
+        let item_name = JsonReader::read_string(obj, "item", error);
+        let item = item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name));
+        let amount = JsonReader::read_u32(obj, "amount", error) as usize;
+        if amount == 0 || amount > STACK_CAPACITY {
+            log::error!("Laying stack amount {} is out of range", amount);
+            *error = true;
+        }
+        LayingObject::new(position, item, amount.clamp(1, STACK_CAPACITY))
     }
 }
 
@@ -46,7 +171,19 @@ impl PhysicsSimulated for LayingObject {
         hierarchy
     }
 
-    fn handle_physics_messages(&mut self, messages: MessageHierarchy) {}
+    fn handle_physics_messages(&mut self, messages: MessageHierarchy) {
+        self.touching = messages
+            .messages
+            .get(&self.body.id)
+            .map(|messages| {
+                messages
+                    .iter()
+                    .filter(|msg| matches!(msg.body, MessageBody::TriggerEntered))
+                    .map(|msg| msg.causer)
+                    .collect()
+            })
+            .unwrap_or_default();
+    }
 
     fn physics_update(&mut self, delta_time: f32) {}
 }
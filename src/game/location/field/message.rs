@@ -11,11 +11,16 @@ pub struct Message {
     pub target: Target,
     pub tick_id: u32,
     pub refund: bool,
+    // First empty cell tried by the directions, the item falls there if no direction accepts it.
+    pub ground_cell: Option<IVec2>,
     pub body: MessageBody,
 }
 
 pub enum Target {
+    // Neighbour cells, tried from the last one.
     Directions(Vec<Direction>),
+    // Explicit cell at any distance, e.g. the exit of an underground belt.
+    Position(IVec2),
 }
 
 #[derive(Clone, Default)]
@@ -1,255 +1,1226 @@
-use crate::game::{
-    common::{
-        direction::Direction,
-        math::{IVec2, Math, Vec2},
-    },
-    game_entity::*,
-    gui::with_gui::*,
-    location::{
-        physics_scene::{BodyCollection, BodyHierarchyRoot, PhysicsSimulated},
-        player::Player,
-    },
-    message::*,
-    renderer::Renderer,
-};
-
-use std::iter::once;
-
-pub mod building;
-pub mod cell;
-pub mod laying_object;
-pub mod message;
-
-use cell::Cell;
-use laying_object::LayingObject;
-
-pub struct Field {
-    min_coord: IVec2,
-    max_coord: IVec2,
-    cells: Vec<Vec<Cell>>,
-    // DEBUG
-    pub player: Player,
-    laying_objects: Vec<LayingObject>,
-}
-
-impl Field {
-    pub fn new(min_coord: IVec2, max_coord: IVec2) -> Field {
-        let x_count = (max_coord.x - min_coord.x + 1) as usize;
-        let y_count = (max_coord.y - min_coord.y + 1) as usize;
-        let mut cells = Vec::with_capacity(x_count);
-        for _ in 0..x_count {
-            let mut cells_row = Vec::with_capacity(y_count);
-            for _ in 0..y_count {
-                cells_row.push(Cell::default());
-            }
-            cells.push(cells_row);
-        }
-
-        let test_laying_objects = vec![
-            LayingObject::new(Vec2::new(5.0, 5.0), 100.0),
-            LayingObject::new(Vec2::new(5.0, 4.0), 10.0),
-            LayingObject::new(Vec2::new(5.0, 3.0), 5.0),
-            LayingObject::new(Vec2::new(5.0, 2.0), 1.0),
-            LayingObject::new(Vec2::new(5.0, 1.0), 0.1),
-        ];
-
-        Field {
-            min_coord,
-            max_coord,
-            cells,
-            player: Player::new(Vec2::new(2.5, 2.5)),
-            laying_objects: test_laying_objects,
-        }
-    }
-
-    pub fn get_cell_mut(&mut self, coords: IVec2) -> Option<&mut Cell> {
-        if coords.x >= self.min_coord.x
-            && coords.x <= self.max_coord.x
-            && coords.y >= self.min_coord.y
-            && coords.y <= self.max_coord.y
-        {
-            return Some(self.get_cell_mut_unchecked(coords));
-        }
-        None
-    }
-
-    fn get_cell_mut_unchecked(&mut self, coords: IVec2) -> &mut Cell {
-        let arr_coords = coords - self.min_coord;
-        &mut self.cells[arr_coords.x as usize][arr_coords.y as usize]
-    }
-
-    // TODO : IT'S DEBUG
-    pub fn process_keyboard_input(&mut self, context: &ggez::Context) {
-        self.player.process_keyboard_input(context);
-    }
-}
-
-impl GameEntity for Field {
-    fn update(&mut self, parameters: &UpdateParameters) {
-        for cell in self.iter_mut() {
-            cell.update(parameters);
-        }
-
-        for laying_obj in &mut self.laying_objects {
-            laying_obj.update(parameters);
-        }
-
-        self.player.update(parameters);
-    }
-
-    fn tick(&mut self, tick_id: u32) {
-        for cell in self.iter_mut() {
-            cell.tick(tick_id);
-        }
-
-        for laying_obj in &mut self.laying_objects {
-            laying_obj.tick(tick_id);
-        }
-
-        self.player.tick(tick_id);
-    }
-
-    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
-        let mut render_bounds = renderer.get_render_bounds();
-        render_bounds.min = transform.reverse().apply(render_bounds.min);
-        render_bounds.max = transform.reverse().apply(render_bounds.max);
-
-        let mut min_visible_cell = render_bounds.min.to_ivec2() - IVec2::new(1, 1);
-        min_visible_cell.x = Math::max(min_visible_cell.x, self.min_coord.x);
-        min_visible_cell.y = Math::max(min_visible_cell.y, self.min_coord.y);
-
-        let mut max_visible_cell = render_bounds.max.to_ivec2() + IVec2::new(1, 1);
-        max_visible_cell.x = Math::min(max_visible_cell.x, self.max_coord.x);
-        max_visible_cell.y = Math::min(max_visible_cell.y, self.max_coord.y);
-
-        for x in min_visible_cell.x..=max_visible_cell.x {
-            for y in min_visible_cell.y..=max_visible_cell.y {
-                let cell_pos = IVec2::new(x as isize, y as isize);
-                let cell_transform = SpriteTransform::default().add_translation(cell_pos.to_vec2());
-                let cell_index = cell_pos - self.min_coord;
-                let cell = &mut self.cells[cell_index.x as usize][cell_index.y as usize];
-                cell.render(renderer, transform.combine(&cell_transform));
-            }
-        }
-
-        for laying_obj in &mut self.laying_objects {
-            laying_obj.render(renderer, transform.clone());
-        }
-
-        self.player.render(renderer, transform);
-    }
-}
-
-impl MessageSender for Field {
-    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
-        let mut messages = vec![];
-        for x in self.min_coord.x..self.max_coord.x {
-            for y in self.min_coord.y..self.max_coord.y {
-                let cell = self.get_cell_mut_unchecked(IVec2::new(x, y));
-                let mut cell_messages = cell.pull_messages(tick_id);
-                for msg in &mut cell_messages {
-                    if let Message::FieldMessage(ref mut msg) = msg {
-                        msg.sender.set_position(IVec2::new(x, y));
-                    }
-                }
-                messages.append(&mut cell_messages);
-            }
-        }
-        messages
-    }
-}
-
-impl MessageReceiver for Field {
-    fn try_push_message(&mut self, mut message: Message) -> Option<Message> {
-        match message {
-            Message::FieldMessage(ref mut msg) => {
-                if msg.refund {
-                    if let Some(refunded) = self
-                        .get_cell_mut_unchecked(msg.sender.get_position())
-                        .try_push_message(message)
-                    {
-                        log::error!("Couldn't process refund message");
-                    }
-                    return None;
-                }
-
-                let sender_pos = msg.sender.get_position();
-                match msg.target {
-                    field_message::Target::Directions(ref mut directions) => {
-                        if directions.len() == 0 {
-                            msg.refund = true;
-                            return Some(message);
-                        }
-
-                        let dir = directions.pop().unwrap();
-                        let receiver_pos = sender_pos + dir.to_ivec2();
-                        msg.receiver.set_position(receiver_pos);
-                        if let Some(receiver_cell) = self.get_cell_mut(receiver_pos) {
-                            receiver_cell.try_push_message(message)
-                        } else {
-                            log::error!("Trying to access non-existing cell");
-                            Some(message)
-                        }
-                    }
-                }
-            }
-            _ => Some(message),
-        }
-    }
-}
-
-// TODO : Automatize in macro.
-impl PhysicsSimulated for Field {
-    fn get_bodies(&mut self) -> BodyHierarchyRoot {
-        BodyHierarchyRoot::new(
-            self.cells
-                .iter_mut()
-                .flatten()
-                .map(|cell| cell.get_bodies())
-                .chain(once(self.player.get_bodies()))
-                .chain(self.laying_objects.iter_mut().map(|obj| obj.get_bodies()))
-                .collect(),
-            BodyCollection::default(),
-        )
-    }
-
-    fn handle_physics_messages(&mut self, mut messages: physics_message::MessageHierarchy) {
-        for laying_obj in self.laying_objects.iter_mut().rev() {
-            laying_obj.handle_physics_messages(messages.nested.pop().unwrap());
-        }
-        self.player
-            .handle_physics_messages(messages.nested.pop().unwrap());
-        let mut cell_messages = messages.nested.into_iter().rev();
-        for cell in self.iter_mut() {
-            cell.handle_physics_messages(cell_messages.next().unwrap());
-        }
-    }
-
-    fn physics_update(&mut self, delta_time: f32) {
-        self.iter_mut()
-            .for_each(|cell| cell.physics_update(delta_time));
-        self.laying_objects
-            .iter_mut()
-            .for_each(|obj| obj.physics_update(delta_time));
-        self.player.physics_update(delta_time);
-    }
-}
-
-impl WithGui for Field {
-    fn render_gui(&mut self, params: &mut GuiRenderParams) {
-        for cell in self.iter_mut() {
-            cell.render_gui(params);
-        }
-        self.player.render_gui(params);
-    }
-}
-
-impl<'a> Field {
-    pub fn iter(&'a self) -> std::iter::Flatten<core::slice::Iter<'a, Vec<Cell>>> {
-        self.cells.iter().flatten()
-    }
-
-    pub fn iter_mut(&'a mut self) -> std::iter::Flatten<core::slice::IterMut<'a, Vec<Cell>>> {
-        self.cells.iter_mut().flatten()
-    }
-}
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::game::{
+    common::{
+        direction::Direction,
+        json_reader::JsonReader,
+        json_writer::JsonWriter,
+        math::{IVec2, Rect, Vec2},
+    },
+    game_entity::*,
+    gui::with_gui::*,
+    location::{
+        cursor::Hover,
+        event::GameEvent,
+        physics_scene::{BodyCollection, BodyHierarchyRoot, PhysicsSimulated},
+        player::Player,
+        save::LoadContext,
+    },
+    message::*,
+    renderer::Renderer,
+};
+
+use std::iter::once;
+
+pub mod building;
+pub mod cell;
+pub mod chunk;
+pub mod fluid;
+pub mod laying_object;
+pub mod message;
+pub mod power;
+pub mod trigger;
+pub mod world_generator;
+
+use building::{facing_from_json_object, item::Item, rotate_size, Building, MAX_BUILDING_SIZE};
+use cell::Cell;
+use chunk::Chunk;
+use fluid::{FluidPortMode, FluidTank};
+use laying_object::{LayingObject, STACK_CAPACITY};
+use power::{ElectricNode, PowerGrid, PowerNetwork};
+use trigger::Trigger;
+use world_generator::WorldGenerator;
+
+// Chunks closer than that(in chunks) to the player are loaded,
+// and to the dynamic bodies take part in physics.
+const CHUNK_LOAD_RADIUS: isize = 1;
+// Player touching the footprint edge doesn't block placement.
+const PLACEMENT_MARGIN: f32 = 0.05;
+
+pub struct Field {
+    // Grows on demand, so the field has no bounds.
+    chunks: HashMap<IVec2, Chunk>,
+    // Fills the newly loaded chunks.
+    generator: WorldGenerator,
+    // DEBUG
+    pub player: Player,
+    laying_objects: Vec<LayingObject>,
+    // Rebuilt every tick from the electric ports of buildings.
+    power_grid: PowerGrid,
+    // Set when buildings are added, removed or turned, the grid is rebuilt on the next tick.
+    power_grid_dirty: bool,
+    // Chunks which bodies were gathered in the last get_bodies call.
+    physics_chunks: Vec<IVec2>,
+    triggers: Vec<Trigger>,
+    // Networks short of power in the last tick, by voltage and the lowest member position,
+    // which doesn't depend on the order of the nodes.
+    power_shortages: Vec<(u32, IVec2)>,
+    // Taken by the location at the end of the tick, with the events of the buildings.
+    events: Vec<GameEvent>,
+}
+
+// Fluid tanks of one building as they were at the start of the tick.
+struct FluidNode {
+    origin: IVec2,
+    size: IVec2,
+    tanks: Vec<FluidTank>,
+    // Sides of each tank.
+    sides: Vec<Vec<Direction>>,
+}
+
+impl Field {
+    pub fn new(generator: WorldGenerator) -> Field {
+        Field {
+            chunks: HashMap::new(),
+            generator,
+            player: Player::new(Vec2::zero()),
+            laying_objects: vec![],
+            power_grid: PowerGrid::default(),
+            power_grid_dirty: true,
+            physics_chunks: vec![],
+            triggers: vec![],
+            power_shortages: vec![],
+            events: vec![],
+        }
+    }
+
+    // Loads all the chunks intersecting the area between min_coord and max_coord(inclusive).
+    pub fn load_area(&mut self, min_coord: IVec2, max_coord: IVec2) {
+        let min_chunk = Chunk::get_chunk_position(min_coord);
+        let max_chunk = Chunk::get_chunk_position(max_coord);
+        for x in min_chunk.x..=max_chunk.x {
+            for y in min_chunk.y..=max_chunk.y {
+                self.load_chunk(IVec2::new(x, y));
+            }
+        }
+    }
+
+    pub fn get_cell(&self, coords: IVec2) -> Option<&Cell> {
+        self.chunks
+            .get(&Chunk::get_chunk_position(coords))
+            .map(|chunk| chunk.get_cell(coords))
+    }
+
+    // None if the cell's chunk isn't loaded.
+    pub fn get_cell_mut(&mut self, coords: IVec2) -> Option<&mut Cell> {
+        self.chunks
+            .get_mut(&Chunk::get_chunk_position(coords))
+            .map(|chunk| chunk.get_cell_mut(coords))
+    }
+
+    pub fn get_or_load_cell_mut(&mut self, coords: IVec2) -> &mut Cell {
+        self.load_chunk(Chunk::get_chunk_position(coords))
+            .get_cell_mut(coords)
+    }
+
+    pub fn get_power_networks(&self) -> &[PowerNetwork] {
+        self.power_grid.get_networks()
+    }
+
+    // Network the building with origin at position is connected to.
+    pub fn get_power_network_at(&self, position: IVec2, voltage: u32) -> Option<&PowerNetwork> {
+        self.power_grid.get_network_at(position, voltage)
+    }
+
+    // None if there's no consumer in the cell.
+    pub fn get_power_satisfaction(&self, position: IVec2) -> Option<f32> {
+        self.power_grid.get_consumer_satisfaction(position)
+    }
+
+    // Fails if any cell of the footprint is occupied or the building refuses the origin cell.
+    pub fn build(&mut self, position: IVec2, building: Box<dyn Building>) -> Result<(), String> {
+        let size = building.get_size();
+        let footprint = Self::get_footprint(position, size);
+        self.check_footprint(&footprint)?;
+
+        self.load_chunk(Chunk::get_chunk_position(position)).build(
+            position,
+            building,
+            Self::get_footprint_center(position, size),
+        )?;
+        for &cell_position in footprint.iter().filter(|&&cell| cell != position) {
+            self.get_or_load_cell_mut(cell_position)
+                .set_building_origin(Some(position));
+        }
+        self.power_grid_dirty = true;
+
+        Ok(())
+    }
+
+    // Same as build, but the player mustn't stand on the footprint.
+    pub fn place_building(
+        &mut self,
+        position: IVec2,
+        building: Box<dyn Building>,
+    ) -> Result<(), String> {
+        self.check_placement(position, building.get_size())?;
+        self.build(position, building)
+    }
+
+    // Whether the building of the size can be placed by the player.
+    pub fn check_placement(&mut self, position: IVec2, size: IVec2) -> Result<(), String> {
+        self.check_footprint(&Self::get_footprint(position, size))?;
+
+        let footprint_rect = Rect::new(
+            position.to_vec2() - Vec2::new_xy(0.5 - PLACEMENT_MARGIN),
+            (position + size).to_vec2() - Vec2::new_xy(0.5 + PLACEMENT_MARGIN),
+        );
+        let player_position = self.player.body.get_position();
+        let player_rect = Rect::new(
+            player_position - Vec2::new_xy(0.5),
+            player_position + Vec2::new_xy(0.5),
+        );
+        if footprint_rect.is_overlap(&player_rect) {
+            return Err(String::from("Player is in the way"));
+        }
+        Ok(())
+    }
+
+    fn check_footprint(&mut self, footprint: &[IVec2]) -> Result<(), String> {
+        for &cell_position in footprint {
+            if self.get_or_load_cell_mut(cell_position).is_occupied() {
+                return Err(format!("Cell {} is occupied", cell_position));
+            }
+        }
+        Ok(())
+    }
+
+    // Removes the building covering the cell from all of its cells.
+    pub fn deconstruct(&mut self, position: IVec2) -> Result<Box<dyn Building>, String> {
+        let origin = self
+            .get_building_origin(position)
+            .ok_or_else(|| format!("No building at {}", position))?;
+        let size = self.get_building_size(origin);
+
+        for &cell_position in Self::get_footprint(origin, size).iter() {
+            self.get_or_load_cell_mut(cell_position)
+                .set_building_origin(None);
+        }
+        self.power_grid_dirty = true;
+        Ok(self
+            .load_chunk(Chunk::get_chunk_position(origin))
+            .take_building(origin)
+            .unwrap())
+    }
+
+    // Takes as much of the building outputs as fits into the player inventory.
+    pub fn take_building_outputs(&mut self, origin: IVec2) {
+        let building = match self
+            .chunks
+            .get_mut(&Chunk::get_chunk_position(origin))
+            .and_then(|chunk| chunk.get_cell_mut(origin).get_building_mut())
+        {
+            Some(building) => building,
+            None => return,
+        };
+
+        let inventory = self.player.get_inventory_mut();
+        for (item, amount) in building.get_output_items() {
+            let amount = amount.min(inventory.get_free_space(item.get_id()));
+            let taken = building.take_output_items(item.get_id(), amount);
+            inventory.try_put_items(item, taken);
+        }
+    }
+
+    // Puts the stack of the inventory slot into the building, the rest stays in the slot.
+    pub fn put_slot_into_building(&mut self, origin: IVec2, slot_id: usize) {
+        let building = match self
+            .chunks
+            .get_mut(&Chunk::get_chunk_position(origin))
+            .and_then(|chunk| chunk.get_cell_mut(origin).get_building_mut())
+        {
+            Some(building) => building,
+            None => return,
+        };
+
+        let inventory = self.player.get_inventory_mut();
+        let accepted = match inventory.get_slot(slot_id) {
+            Some((item, amount)) => building.put_input_items(item, amount),
+            None => return,
+        };
+        inventory.take_from_slot(slot_id, accepted);
+    }
+
+    // Labs get the items left to pay, craft stations the recipes to lock.
+    pub fn set_research(&mut self, demand: &[(Item, usize)], locked_recipes: &[String]) {
+        for chunk in Self::sorted_chunks_mut(&mut self.chunks, Chunk::is_active) {
+            for (_, cell) in chunk.iter_mut() {
+                if let Some(building) = cell.get_building_mut() {
+                    if let Some(lab) = building.as_lab_mut() {
+                        lab.set_research_demand(demand);
+                    }
+                    if let Some(station) = building.as_recipe_lock_mut() {
+                        station.set_locked_recipes(locked_recipes);
+                    }
+                }
+            }
+        }
+    }
+
+    // Items consumed by the labs this tick, in position order.
+    pub fn take_research_items(&mut self) -> Vec<(Item, usize)> {
+        let mut items = vec![];
+        for chunk in Self::sorted_chunks_mut(&mut self.chunks, Chunk::is_active) {
+            for (_, cell) in chunk.iter_mut() {
+                if let Some(lab) = cell
+                    .get_building_mut()
+                    .and_then(|building| building.as_lab_mut())
+                {
+                    items.append(&mut lab.take_research_items());
+                }
+            }
+        }
+        items
+    }
+
+    // Drops the stack of the inventory slot in front of the player, the rest stays in the slot.
+    pub fn drop_slot(&mut self, slot_id: usize) {
+        let position = self.player.get_facing_cell_position();
+        let (item, amount) = match self.player.get_inventory_mut().get_slot(slot_id) {
+            Some((item, amount)) => (item.clone(), amount),
+            None => return,
+        };
+
+        let dropped = self.put_on_ground(position, &item, amount);
+        if dropped > 0 {
+            self.player
+                .get_inventory_mut()
+                .take_from_slot(slot_id, dropped);
+            if let Some(obj) = self.get_laying_object_at_mut(position) {
+                obj.block_pickup();
+            }
+        }
+    }
+
+    // Items lie in a single stack per cell, only in the cells free of buildings.
+    // Returns the amount put.
+    pub fn put_on_ground(&mut self, position: IVec2, item: &Item, amount: usize) -> usize {
+        match self.get_cell(position) {
+            Some(cell) if !cell.is_occupied() => {}
+            _ => return 0,
+        }
+
+        match self.get_laying_object_at_mut(position) {
+            Some(obj) => obj.add_items(item, amount),
+            None => {
+                let amount = amount.min(STACK_CAPACITY);
+                if amount > 0 {
+                    self.laying_objects.push(LayingObject::new(
+                        position.to_vec2(),
+                        item.clone(),
+                        amount,
+                    ));
+                }
+                amount
+            }
+        }
+    }
+
+    pub fn get_laying_object_at(&self, position: IVec2) -> Option<&LayingObject> {
+        self.laying_objects
+            .iter()
+            .find(|obj| obj.get_cell_position() == position)
+    }
+
+    fn get_laying_object_at_mut(&mut self, position: IVec2) -> Option<&mut LayingObject> {
+        self.laying_objects
+            .iter_mut()
+            .find(|obj| obj.get_cell_position() == position)
+    }
+
+    // Stacks touched by the player go to the inventory.
+    fn pick_up_laying_objects(&mut self) {
+        let player_body_id = self.player.body.id;
+        let inventory = self.player.get_inventory_mut();
+        for obj in &mut self.laying_objects {
+            obj.try_pick_up(player_body_id, inventory);
+        }
+        self.laying_objects.retain(|obj| !obj.is_empty());
+    }
+
+    // Turns the building covering the cell clockwise around its origin, fails if the cells
+    // added by the turn can't be built on, the same way as for placement.
+    pub fn rotate_building(&mut self, position: IVec2) -> Result<(), String> {
+        let origin = self
+            .get_building_origin(position)
+            .ok_or_else(|| format!("No building at {}", position))?;
+        let building = self.get_cell(origin).unwrap().get_building().unwrap();
+        let facing = building.get_facing().rotate_clockwise();
+        let size = building.get_size();
+        let old_footprint = Self::get_footprint(origin, size);
+        let new_size = rotate_size(size, Direction::Right);
+        let new_footprint = Self::get_footprint(origin, new_size);
+
+        for &cell_position in &new_footprint {
+            if !old_footprint.contains(&cell_position) {
+                self.check_placement(cell_position, IVec2::new(1, 1))?;
+            }
+        }
+
+        for &cell_position in old_footprint.iter().filter(|&&cell| cell != origin) {
+            self.get_or_load_cell_mut(cell_position)
+                .set_building_origin(None);
+        }
+        for &cell_position in new_footprint.iter().filter(|&&cell| cell != origin) {
+            self.get_or_load_cell_mut(cell_position)
+                .set_building_origin(Some(origin));
+        }
+        self.get_or_load_cell_mut(origin)
+            .rotate_building(facing, Self::get_footprint_center(origin, new_size));
+        self.power_grid_dirty = true;
+
+        Ok(())
+    }
+
+    pub fn get_cell_position_at(point: Vec2) -> IVec2 {
+        point.to_ivec2_rounded()
+    }
+
+    // Laying objects lie over the buildings, which cover the cells.
+    pub fn pick(&self, point: Vec2) -> Hover {
+        if let Some(obj) = self
+            .laying_objects
+            .iter()
+            .rev()
+            .find(|obj| obj.contains_point(point))
+        {
+            return Hover::LayingObject(obj.get_cell_position());
+        }
+
+        let position = Self::get_cell_position_at(point);
+        match self.get_building_origin(position) {
+            Some(origin) => Hover::Building(origin),
+            None => Hover::Cell(position),
+        }
+    }
+
+    // Origin cell of the building covering the cell, if any.
+    pub fn get_building_origin(&self, position: IVec2) -> Option<IVec2> {
+        let cell = self.get_cell(position)?;
+        if cell.get_building().is_some() {
+            Some(position)
+        } else {
+            cell.get_building_origin()
+        }
+    }
+
+    pub fn add_laying_object(&mut self, laying_object: LayingObject) {
+        self.laying_objects.push(laying_object);
+    }
+
+    pub fn add_trigger(&mut self, trigger: Trigger) {
+        self.triggers.push(trigger);
+    }
+
+    // Events of the field go first, then the ones of the buildings in position order.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        let mut events = std::mem::take(&mut self.events);
+        for chunk in Self::sorted_chunks_mut(&mut self.chunks, Chunk::is_active) {
+            for (position, cell) in chunk.iter_mut() {
+                if let Some(building) = cell.get_building_mut() {
+                    events.extend(
+                        building
+                            .take_events()
+                            .into_iter()
+                            .map(|event| event.at(position)),
+                    );
+                }
+            }
+        }
+        events
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        let chunks: Vec<serde_json::Value> = Self::sorted_chunks(&self.chunks, |_| true)
+            .into_iter()
+            .map(|chunk| {
+                json!({
+                    "position": JsonWriter::write_ivec2(chunk.get_position()),
+                    "cells": chunk
+                        .iter()
+                        .map(|(_, cell)| cell.save_state())
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        json!({
+            "generator": self.generator.save_state(),
+            "chunks": chunks,
+            "player": self.player.save_state(),
+            "laying_objects": self
+                .laying_objects
+                .iter()
+                .map(|obj| obj.save_state())
+                .collect::<Vec<_>>(),
+            "triggers": self
+                .triggers
+                .iter()
+                .map(|trigger| trigger.save_state())
+                .collect::<Vec<_>>(),
+            // Shortages going on at the save aren't reported again after the load.
+            "power_shortages": self
+                .power_shortages
+                .iter()
+                .map(|(voltage, member)| json!({
+                    "voltage": voltage,
+                    "member": JsonWriter::write_ivec2(*member),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        context: &LoadContext,
+        error: &mut bool,
+    ) -> Field {
+        let generator_state = JsonReader::read_obj(state, "generator", error);
+        let generator = WorldGenerator::from_saved_state(&generator_state, context, error);
+
+        let mut field = Field::new(generator);
+
+        // Buildings are placed after all the cells are loaded, as they may cover several chunks.
+        let mut saved_buildings = vec![];
+        for saved_chunk in JsonReader::read_vec(state, "chunks", error) {
+            let position = JsonReader::read_ivec2(&saved_chunk, "position", error);
+            let saved_cells = JsonReader::read_vec(&saved_chunk, "cells", error);
+
+            let chunk = field.load_chunk(position);
+            let mut cell_count = 0;
+            for ((cell_position, cell), saved_cell) in chunk.iter_mut().zip(saved_cells.iter()) {
+                *cell = Cell::from_saved_state(saved_cell, context, error);
+                match saved_cell.get("building") {
+                    None | Some(serde_json::Value::Null) => {}
+                    Some(saved_building) => {
+                        saved_buildings.push((cell_position, saved_building.clone()))
+                    }
+                }
+                cell_count += 1;
+            }
+            if cell_count != saved_cells.len() {
+                *error = true;
+            }
+        }
+
+        for (position, saved_building) in saved_buildings {
+            field.restore_building(position, &saved_building, context, error);
+        }
+
+        let player_state = JsonReader::read_obj(state, "player", error);
+        field
+            .player
+            .load_state(&player_state, &context.item_factory, error);
+
+        field.laying_objects = JsonReader::read_vec(state, "laying_objects", error)
+            .iter()
+            .map(|obj| LayingObject::from_saved_state(obj, &context.item_factory, error))
+            .collect();
+
+        field.triggers = JsonReader::read_vec(state, "triggers", error)
+            .iter()
+            .map(|trigger| Trigger::from_saved_state(trigger, error))
+            .collect();
+
+        field.power_shortages = JsonReader::read_vec(state, "power_shortages", error)
+            .iter()
+            .map(|shortage| {
+                (
+                    JsonReader::read_u32(shortage, "voltage", error),
+                    JsonReader::read_ivec2(shortage, "member", error),
+                )
+            })
+            .collect();
+
+        field
+    }
+
+    fn restore_building(
+        &mut self,
+        position: IVec2,
+        saved_building: &serde_json::Value,
+        context: &LoadContext,
+        error: &mut bool,
+    ) {
+        let name = JsonReader::read_string(saved_building, "name", error);
+        let facing = facing_from_json_object(saved_building, error);
+        let building_state = JsonReader::read_obj(saved_building, "state", error);
+        if !context.building_registry.contains(&name) {
+            *error = true;
+        }
+
+        let mut building = context.building_registry.create(&name);
+        building.set_facing(facing);
+        if let Err(e) = self.build(position, building) {
+            log::error!("Building {} can't be restored : {}", name, e);
+            *error = true;
+            return;
+        }
+
+        if let Some(building) = self
+            .get_cell_mut(position)
+            .and_then(|cell| cell.get_building_mut())
+        {
+            building.load_state(&building_state, &context.item_factory, error);
+        }
+    }
+
+    fn get_footprint(origin: IVec2, size: IVec2) -> Vec<IVec2> {
+        let mut footprint = vec![];
+        for x in 0..size.x {
+            for y in 0..size.y {
+                footprint.push(origin + IVec2::new(x, y));
+            }
+        }
+        footprint
+    }
+
+    pub fn get_footprint_center(origin: IVec2, size: IVec2) -> Vec2 {
+        origin.to_vec2() + (size.to_vec2() - Vec2::new_xy(1.0)) * 0.5
+    }
+
+    pub fn get_building_size(&self, origin: IVec2) -> IVec2 {
+        self.get_cell(origin)
+            .and_then(|cell| cell.get_building())
+            .map_or(IVec2::new(1, 1), |building| building.get_size())
+    }
+
+    // Messages to the cell covered by a multi-cell building go to the building.
+    fn push_to_cell(&mut self, position: IVec2, message: Message) -> Option<Message> {
+        let position = self.get_building_origin(position).unwrap_or(position);
+        // Cells of not loaded chunks are empty, so they don't accept anything.
+        match self.get_cell_mut(position) {
+            Some(cell) => cell.try_push_message(message),
+            None => Some(message),
+        }
+    }
+
+    fn is_free_cell(&self, position: IVec2) -> bool {
+        matches!(self.get_cell(position), Some(cell) if !cell.is_occupied())
+    }
+
+    // Item refused by all the directions falls to the first empty cell tried,
+    // otherwise it goes back to the sender.
+    fn eject_or_refund(&mut self, message: Message) -> Option<Message> {
+        let Message::FieldMessage(mut msg) = message;
+        if let Some(position) = msg.ground_cell {
+            let item = match &msg.body {
+                field_message::MessageBody::PushItem(item) => item.get_item().clone(),
+            };
+            if self.put_on_ground(position, &item, 1) == 1 {
+                return None;
+            }
+        }
+
+        msg.refund = true;
+        Some(Message::FieldMessage(msg))
+    }
+
+    fn load_chunk(&mut self, position: IVec2) -> &mut Chunk {
+        let generator = &self.generator;
+        self.chunks
+            .entry(position)
+            .or_insert_with(|| Chunk::new(position, generator))
+    }
+
+    fn load_chunks_around_player(&mut self) {
+        let player_chunk = Chunk::get_chunk_position(self.player.body.get_position().to_ivec2());
+        for x in -CHUNK_LOAD_RADIUS..=CHUNK_LOAD_RADIUS {
+            for y in -CHUNK_LOAD_RADIUS..=CHUNK_LOAD_RADIUS {
+                self.load_chunk(player_chunk + IVec2::new(x, y));
+            }
+        }
+    }
+
+    // Chunks ordered by position, so that processing order doesn't depend on HashMap.
+    fn sorted_chunks(
+        chunks: &HashMap<IVec2, Chunk>,
+        filter: impl Fn(&Chunk) -> bool,
+    ) -> Vec<&Chunk> {
+        let mut chunks: Vec<&Chunk> = chunks.values().filter(|chunk| filter(chunk)).collect();
+        chunks.sort_by_key(|chunk| (chunk.get_position().x, chunk.get_position().y));
+        chunks
+    }
+
+    fn sorted_chunks_mut(
+        chunks: &mut HashMap<IVec2, Chunk>,
+        filter: impl Fn(&Chunk) -> bool,
+    ) -> Vec<&mut Chunk> {
+        let mut chunks: Vec<&mut Chunk> =
+            chunks.values_mut().filter(|chunk| filter(chunk)).collect();
+        chunks.sort_by_key(|chunk| (chunk.get_position().x, chunk.get_position().y));
+        chunks
+    }
+
+    // Chunks near the player or the laying objects, the only ones where collisions may happen.
+    fn get_physics_chunk_positions(&self) -> Vec<IVec2> {
+        let mut positions = vec![];
+        let dynamic_positions = once(self.player.body.get_position()).chain(
+            self.laying_objects
+                .iter()
+                .map(|obj| obj.body.get_position()),
+        );
+        for position in dynamic_positions {
+            let center = Chunk::get_chunk_position(position.to_ivec2());
+            for x in -CHUNK_LOAD_RADIUS..=CHUNK_LOAD_RADIUS {
+                for y in -CHUNK_LOAD_RADIUS..=CHUNK_LOAD_RADIUS {
+                    let chunk_position = center + IVec2::new(x, y);
+                    if self.chunks.contains_key(&chunk_position)
+                        && !positions.contains(&chunk_position)
+                    {
+                        positions.push(chunk_position);
+                    }
+                }
+            }
+        }
+        positions.sort_by_key(|position| (position.x, position.y));
+        positions
+    }
+
+    // Each underground entrance is linked to the nearest exit ahead facing the same way.
+    fn link_tunnels(&mut self) {
+        let mut entrances = vec![];
+        for chunk in Self::sorted_chunks(&self.chunks, Chunk::is_active) {
+            for (position, cell) in chunk.iter() {
+                if let Some(building) = cell.get_building() {
+                    if let Some(range) = building
+                        .as_tunnel()
+                        .and_then(|tunnel| tunnel.get_tunnel_range())
+                    {
+                        entrances.push((position, building.get_facing(), range));
+                    }
+                }
+            }
+        }
+
+        for (position, facing, range) in entrances {
+            let exit = (1..=range as isize)
+                .map(|distance| position + facing.to_ivec2() * distance)
+                .find(|&cell_position| {
+                    self.get_cell(cell_position)
+                        .and_then(|cell| cell.get_building())
+                        .filter(|building| building.get_facing() == facing)
+                        .and_then(|building| building.as_tunnel())
+                        .filter(|tunnel| tunnel.is_tunnel_exit())
+                        .is_some()
+                });
+            if let Some(tunnel) = self
+                .get_cell_mut(position)
+                .and_then(|cell| cell.get_building_mut())
+                .and_then(|building| building.as_tunnel_mut())
+            {
+                tunnel.set_tunnel_exit(exit);
+            }
+        }
+    }
+
+    // Inserter arms waiting behind take one item of the building there, belts offer their slots.
+    fn feed_inserters(&mut self) {
+        let mut inserters = vec![];
+        for chunk in Self::sorted_chunks(&self.chunks, Chunk::is_active) {
+            for (position, cell) in chunk.iter() {
+                if let Some(building) = cell.get_building() {
+                    let waiting = building
+                        .as_inserter()
+                        .filter(|inserter| inserter.is_waiting_to_pick_up())
+                        .is_some();
+                    if waiting {
+                        inserters.push((position, building.get_facing()));
+                    }
+                }
+            }
+        }
+
+        for (position, facing) in inserters {
+            let source = match self.get_building_origin(position - facing.to_ivec2()) {
+                Some(source) => source,
+                None => continue,
+            };
+            let inserter = self
+                .get_cell(position)
+                .and_then(|cell| cell.get_building())
+                .and_then(|building| building.as_inserter())
+                .unwrap();
+            let source_building = self.get_cell(source).unwrap().get_building().unwrap();
+            let offered = match source_building.as_belt() {
+                Some(belt) => belt
+                    .get_slot_items()
+                    .into_iter()
+                    .map(|item| (item, 1))
+                    .collect(),
+                None => source_building.get_output_items(),
+            };
+            let item = offered
+                .into_iter()
+                .find(|(item, amount)| *amount > 0 && inserter.accepts_pick_up(item));
+            let item = match item {
+                Some((item, _)) => item,
+                None => continue,
+            };
+
+            let source_building = self
+                .get_cell_mut(source)
+                .and_then(|cell| cell.get_building_mut())
+                .unwrap();
+            let taken = match source_building.as_belt_mut() {
+                Some(belt) => belt.take_slot_item(item.get_id()),
+                None => source_building.take_output_items(item.get_id(), 1) == 1,
+            };
+            if taken {
+                if let Some(inserter) = self
+                    .get_cell_mut(position)
+                    .and_then(|cell| cell.get_building_mut())
+                    .and_then(|building| building.as_inserter_mut())
+                {
+                    inserter.pick_up(item);
+                }
+            }
+        }
+    }
+
+    // Fluids flow between the tanks of the same fluid in neighbour buildings,
+    // if both have the ports on the touching sides.
+    // Flows are computed from the volumes at the start of the tick,
+    // a tank short of fluid or space shares it among its links.
+    fn update_fluid_flow(&mut self) {
+        let mut nodes = vec![];
+        for chunk in Self::sorted_chunks(&self.chunks, Chunk::is_active) {
+            for (position, cell) in chunk.iter() {
+                if let Some(building) = cell.get_building() {
+                    let tanks = building.get_fluid_tanks();
+                    if !tanks.is_empty() {
+                        nodes.push(FluidNode {
+                            origin: position,
+                            size: building.get_size(),
+                            tanks: tanks.to_vec(),
+                            sides: tanks
+                                .iter()
+                                .map(|tank| building.get_fluid_sides(tank.mode))
+                                .collect(),
+                        });
+                    }
+                }
+            }
+        }
+        let node_ids: HashMap<IVec2, usize> = nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (node.origin, id))
+            .collect();
+
+        // (node id, tank id) pairs, each connection is listed both ways.
+        let mut links = vec![];
+        for (a, node) in nodes.iter().enumerate() {
+            let footprint = Self::get_footprint(node.origin, node.size);
+            for dir in [
+                Direction::Up,
+                Direction::Right,
+                Direction::Down,
+                Direction::Left,
+            ] {
+                let mut neighbours: Vec<usize> = footprint
+                    .iter()
+                    .map(|&cell| cell + dir.to_ivec2())
+                    .filter(|cell| !footprint.contains(cell))
+                    .filter_map(|cell| self.get_building_origin(cell))
+                    .filter_map(|origin| node_ids.get(&origin).copied())
+                    .collect();
+                neighbours.sort_unstable();
+                neighbours.dedup();
+
+                for b in neighbours {
+                    for (a_tank, tank) in node.tanks.iter().enumerate() {
+                        if !node.sides[a_tank].contains(&dir) {
+                            continue;
+                        }
+                        let other = &nodes[b];
+                        if let Some(b_tank) = (0..other.tanks.len()).find(|&b_tank| {
+                            other.tanks[b_tank].fluid.get_id() == tank.fluid.get_id()
+                                && other.sides[b_tank].contains(&dir.negate())
+                        }) {
+                            links.push(((a, a_tank), (b, b_tank)));
+                        }
+                    }
+                }
+            }
+        }
+
+        let tank = |(node, tank): (usize, usize)| &nodes[node].tanks[tank];
+        let mut link_ids_by_sender: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        let mut link_ids_by_receiver: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for (link_id, &(from, to)) in links.iter().enumerate() {
+            link_ids_by_sender.entry(from).or_default().push(link_id);
+            link_ids_by_receiver.entry(to).or_default().push(link_id);
+        }
+
+        let mut flows: Vec<u32> = links
+            .iter()
+            .map(|&(from, to)| {
+                let flow = tank(from).get_flow(tank(to));
+                // Levels are evened out gradually, so that they don't swing back and forth.
+                if tank(from).mode == FluidPortMode::Both && tank(to).mode == FluidPortMode::Both {
+                    flow / (link_ids_by_sender[&from].len() as u32 + 1)
+                } else {
+                    flow
+                }
+            })
+            .collect();
+        let mut limit_flows = |total: u32, link_ids: &[usize]| {
+            let mut amounts: Vec<u32> = link_ids.iter().map(|&id| flows[id]).collect();
+            fluid::share(total, &mut amounts);
+            for (&id, amount) in link_ids.iter().zip(amounts) {
+                flows[id] = amount;
+            }
+        };
+        for (&from, link_ids) in &link_ids_by_sender {
+            limit_flows(tank(from).volume, link_ids);
+        }
+        for (&to, link_ids) in &link_ids_by_receiver {
+            limit_flows(tank(to).get_free_space(), link_ids);
+        }
+
+        let mut volumes: Vec<Vec<u32>> = nodes
+            .iter()
+            .map(|node| node.tanks.iter().map(|tank| tank.volume).collect())
+            .collect();
+        for (&((from_node, from_tank), (to_node, to_tank)), flow) in links.iter().zip(flows) {
+            volumes[from_node][from_tank] -= flow;
+            volumes[to_node][to_tank] += flow;
+        }
+
+        for (node, volumes) in nodes.iter().zip(volumes) {
+            if let Some(building) = self
+                .get_cell_mut(node.origin)
+                .and_then(|cell| cell.get_building_mut())
+            {
+                for (tank, volume) in building.get_fluid_tanks_mut().iter_mut().zip(volumes) {
+                    tank.volume = volume;
+                }
+            }
+        }
+    }
+
+    // Networks are reported once when the shortage starts.
+    fn report_power_shortages(&mut self) {
+        let mut shortages = vec![];
+        for network in self.power_grid.get_networks() {
+            if network.production >= network.consumption {
+                continue;
+            }
+            let key = match network
+                .members
+                .iter()
+                .min_by_key(|member| (member.x, member.y))
+            {
+                Some(&member) => (network.voltage, member),
+                None => continue,
+            };
+            if !self.power_shortages.contains(&key) {
+                self.events.push(GameEvent::PowerShortage {
+                    voltage: network.voltage,
+                    production: network.production,
+                    consumption: network.consumption,
+                });
+            }
+            shortages.push(key);
+        }
+        self.power_shortages = shortages;
+    }
+
+    fn update_triggers(&mut self) {
+        let cell = Self::get_cell_position_at(self.player.body.get_position());
+        for trigger in self.triggers.iter_mut() {
+            if trigger.update_player_cell(cell) {
+                self.events.push(GameEvent::PlayerEnteredTrigger {
+                    name: String::from(trigger.get_name()),
+                });
+            }
+        }
+    }
+
+    fn update_power_grid(&mut self) {
+        let mut nodes = vec![];
+        let mut consumers = vec![];
+        for chunk in Self::sorted_chunks(&self.chunks, Chunk::is_active) {
+            for (position, cell) in chunk.iter() {
+                if let Some(building) = cell.get_building() {
+                    nodes.append(&mut ElectricNode::from_building(position, building));
+                    consumers.push(position);
+                }
+            }
+        }
+
+        if self.power_grid_dirty {
+            self.power_grid = PowerGrid::new(&nodes);
+            self.power_grid_dirty = false;
+        } else {
+            self.power_grid.update_energy(&nodes);
+        }
+        self.report_power_shortages();
+
+        for position in consumers {
+            if let Some(satisfaction) = self.power_grid.get_consumer_satisfaction(position) {
+                if let Some(building) = self
+                    .get_cell_mut(position)
+                    .and_then(|cell| cell.get_building_mut())
+                {
+                    building.set_power_satisfaction(satisfaction);
+                }
+            }
+        }
+    }
+}
+
+impl GameEntity for Field {
+    fn update(&mut self, parameters: &UpdateParameters) {
+        self.load_chunks_around_player();
+
+        for chunk in Self::sorted_chunks_mut(&mut self.chunks, Chunk::is_active) {
+            for (_, cell) in chunk.iter_mut() {
+                cell.update(parameters);
+            }
+        }
+
+        for laying_obj in &mut self.laying_objects {
+            laying_obj.update(parameters);
+        }
+
+        self.player.update(parameters);
+    }
+
+    fn tick(&mut self, tick_id: u32) {
+        self.update_power_grid();
+        self.update_fluid_flow();
+        self.link_tunnels();
+        self.feed_inserters();
+
+        for chunk in Self::sorted_chunks_mut(&mut self.chunks, Chunk::is_active) {
+            for (_, cell) in chunk.iter_mut() {
+                cell.tick(tick_id);
+            }
+        }
+
+        for laying_obj in &mut self.laying_objects {
+            laying_obj.tick(tick_id);
+        }
+
+        self.player.tick(tick_id);
+        self.update_triggers();
+    }
+
+    fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let mut render_bounds = renderer.get_render_bounds();
+        render_bounds.min = transform.reverse().apply(render_bounds.min);
+        render_bounds.max = transform.reverse().apply(render_bounds.max);
+
+        let min_visible_cell = render_bounds.min.to_ivec2() - IVec2::new(1, 1);
+        let max_visible_cell = render_bounds.max.to_ivec2() + IVec2::new(1, 1);
+
+        for x in min_visible_cell.x..=max_visible_cell.x {
+            for y in min_visible_cell.y..=max_visible_cell.y {
+                let cell_pos = IVec2::new(x as isize, y as isize);
+                if let Some(cell) = self.get_cell_mut(cell_pos) {
+                    let cell_transform =
+                        SpriteTransform::default().add_translation(cell_pos.to_vec2());
+                    cell.render_surface(renderer, transform.combine(&cell_transform));
+                }
+            }
+        }
+
+        // Buildings go over all the surfaces, origins of the multi-cell ones may be out of bounds.
+        let min_origin_cell = min_visible_cell - IVec2::new(1, 1) * (MAX_BUILDING_SIZE - 1);
+        for x in min_origin_cell.x..=max_visible_cell.x {
+            for y in min_origin_cell.y..=max_visible_cell.y {
+                let cell_pos = IVec2::new(x as isize, y as isize);
+                if let Some(cell) = self.get_cell_mut(cell_pos) {
+                    let cell_transform =
+                        SpriteTransform::default().add_translation(cell_pos.to_vec2());
+                    cell.render_building(renderer, transform.combine(&cell_transform));
+                }
+            }
+        }
+
+        for laying_obj in &mut self.laying_objects {
+            laying_obj.render(renderer, transform.clone());
+        }
+
+        self.player.render(renderer, transform);
+    }
+}
+
+impl MessageSender for Field {
+    // Messages of all the cells, ordered by the sender position(x, then y), then by id.
+    // Messages of one sender with the same id keep the order they were pulled in.
+    fn pull_messages(&mut self, tick_id: u32) -> Vec<Message> {
+        let mut messages = vec![];
+        for chunk in Self::sorted_chunks_mut(&mut self.chunks, Chunk::is_active) {
+            for (position, cell) in chunk.iter_mut() {
+                let mut cell_messages = cell.pull_messages(tick_id);
+                for Message::FieldMessage(msg) in &mut cell_messages {
+                    msg.sender.set_position(position);
+                }
+                messages.append(&mut cell_messages);
+            }
+        }
+
+        messages.sort_by_key(|msg| match msg {
+            Message::FieldMessage(msg) => {
+                let position = msg.sender.get_position();
+                (position.x, position.y, msg.id)
+            }
+        });
+        messages
+    }
+}
+
+impl MessageReceiver for Field {
+    fn try_push_message(&mut self, mut message: Message) -> Option<Message> {
+        let Message::FieldMessage(ref mut msg) = message;
+        if msg.refund {
+            let sender_pos = msg.sender.get_position();
+            if self.push_to_cell(sender_pos, message).is_some() {
+                log::error!("Couldn't process refund message");
+            }
+            return None;
+        }
+
+        let sender_pos = msg.sender.get_position();
+        match msg.target {
+            field_message::Target::Directions(ref mut directions) => {
+                if directions.is_empty() {
+                    return self.eject_or_refund(message);
+                }
+
+                let dir = directions.pop().unwrap();
+                let origin = self.get_building_origin(sender_pos).unwrap_or(sender_pos);
+                let size = self.get_building_size(origin);
+                self.push_from_side(message, origin, size, dir)
+            }
+            // The only cell to try, refunded if it doesn't accept.
+            field_message::Target::Position(position) => {
+                msg.receiver.set_position(position);
+                let Message::FieldMessage(mut msg) = self.push_to_cell(position, message)?;
+                msg.refund = true;
+                Some(Message::FieldMessage(msg))
+            }
+        }
+    }
+}
+
+impl Field {
+    // Multi-cell sender tries all the cells along the side, the first to accept gets the message.
+    fn push_from_side(
+        &mut self,
+        mut message: Message,
+        origin: IVec2,
+        size: IVec2,
+        dir: Direction,
+    ) -> Option<Message> {
+        let footprint = Self::get_footprint(origin, size);
+        let side = footprint
+            .iter()
+            .filter(|&&cell| !footprint.contains(&(cell + dir.to_ivec2())));
+
+        for &sender_pos in side {
+            let receiver_pos = sender_pos + dir.to_ivec2();
+            let Message::FieldMessage(ref mut msg) = message;
+            msg.sender.set_position(sender_pos);
+            msg.receiver.set_position(receiver_pos);
+            if msg.ground_cell.is_none() && self.is_free_cell(receiver_pos) {
+                msg.ground_cell = Some(receiver_pos);
+            }
+
+            message = self.push_to_cell(receiver_pos, message)?;
+        }
+
+        Some(message)
+    }
+}
+
+// TODO : Automatize in macro.
+impl PhysicsSimulated for Field {
+    fn get_bodies(&mut self) -> BodyHierarchyRoot {
+        self.physics_chunks = self.get_physics_chunk_positions();
+        let physics_chunks = &self.physics_chunks;
+
+        BodyHierarchyRoot::new(
+            Self::sorted_chunks_mut(&mut self.chunks, |chunk| {
+                physics_chunks.contains(&chunk.get_position())
+            })
+            .into_iter()
+            .map(|chunk| chunk.get_bodies())
+            .chain(once(self.player.get_bodies()))
+            .chain(self.laying_objects.iter_mut().map(|obj| obj.get_bodies()))
+            .collect(),
+            BodyCollection::default(),
+        )
+    }
+
+    fn handle_physics_messages(&mut self, mut messages: physics_message::MessageHierarchy) {
+        for laying_obj in self.laying_objects.iter_mut().rev() {
+            laying_obj.handle_physics_messages(messages.nested.pop().unwrap());
+        }
+        self.player
+            .handle_physics_messages(messages.nested.pop().unwrap());
+        self.pick_up_laying_objects();
+
+        let physics_chunks = &self.physics_chunks;
+        let chunks = Self::sorted_chunks_mut(&mut self.chunks, |chunk| {
+            physics_chunks.contains(&chunk.get_position())
+        });
+        for (chunk, chunk_messages) in chunks.into_iter().zip(messages.nested.into_iter()) {
+            chunk.handle_physics_messages(chunk_messages);
+        }
+    }
+
+    fn physics_update(&mut self, delta_time: f32) {
+        let physics_chunks = &self.physics_chunks;
+        Self::sorted_chunks_mut(&mut self.chunks, |chunk| {
+            physics_chunks.contains(&chunk.get_position())
+        })
+        .into_iter()
+        .for_each(|chunk| chunk.physics_update(delta_time));
+        self.laying_objects
+            .iter_mut()
+            .for_each(|obj| obj.physics_update(delta_time));
+        self.player.physics_update(delta_time);
+    }
+}
+
+// Buildings gui is rendered only in the inspector of the selected one.
+impl WithGui for Field {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        self.player.render_gui(params);
+
+        if let Some(slot_id) = self.player.get_inventory_mut().take_drop_request() {
+            self.drop_slot(slot_id);
+        }
+    }
+}
+
+impl Field {
+    // All the cells of the loaded chunks.
+    pub fn iter(&self) -> impl Iterator<Item = &Cell> {
+        Self::sorted_chunks(&self.chunks, |_| true)
+            .into_iter()
+            .flat_map(|chunk| chunk.iter().map(|(_, cell)| cell))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Cell> {
+        Self::sorted_chunks_mut(&mut self.chunks, |_| true)
+            .into_iter()
+            .flat_map(|chunk| chunk.iter_mut().map(|(_, cell)| cell))
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use crate::game::common::{json_reader::JsonReader, math::IVec2};
+use crate::game::location::field::building::{footprint_distance, Building};
+
+// Ports of buildings placed in neighbour cells are connected.
+pub const ADJACENT_RANGE: u32 = 1;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ElectricPortMode {
+    In,
+    Out,
+    // Only connects others, e.g. electric pole.
+    Transit,
+}
+
+// Energy is measured per tick.
+#[derive(Clone)]
+pub struct ElectricPort {
+    pub mode: ElectricPortMode,
+    pub voltage: u32,
+    pub energy: u32,
+}
+
+impl ElectricPort {
+    pub fn from_json_object(obj: &serde_json::Value, error: &mut bool) -> ElectricPort {
+        let mode = match JsonReader::read_string(obj, "mode", error).as_str() {
+            "in" => ElectricPortMode::In,
+            "out" => ElectricPortMode::Out,
+            _ => {
+                *error = true;
+                ElectricPortMode::Transit
+            }
+        };
+
+        ElectricPort {
+            mode,
+            voltage: JsonReader::read_u32(obj, "voltage", error),
+            energy: JsonReader::read_u32(obj, "energy", error),
+        }
+    }
+}
+
+// All the ports of a building with the same voltage.
+pub struct ElectricNode {
+    // Origin cell of the building.
+    position: IVec2,
+    size: IVec2,
+    voltage: u32,
+    range: u32,
+    is_consumer: bool,
+    production: u32,
+    consumption: u32,
+}
+
+impl ElectricNode {
+    pub fn from_building(position: IVec2, building: &dyn Building) -> Vec<ElectricNode> {
+        let mut nodes: Vec<ElectricNode> = vec![];
+        let active = building.is_electric_active();
+
+        for port in building.get_electric_ports() {
+            let node_id = match nodes.iter().position(|node| node.voltage == port.voltage) {
+                Some(id) => id,
+                None => {
+                    nodes.push(ElectricNode {
+                        position,
+                        size: building.get_size(),
+                        voltage: port.voltage,
+                        range: building.get_electric_range(),
+                        is_consumer: false,
+                        production: 0,
+                        consumption: 0,
+                    });
+                    nodes.len() - 1
+                }
+            };
+
+            let node = &mut nodes[node_id];
+            match port.mode {
+                ElectricPortMode::In => {
+                    node.is_consumer = true;
+                    if active {
+                        node.consumption += port.energy;
+                    }
+                }
+                ElectricPortMode::Out => {
+                    if active {
+                        node.production += port.energy;
+                    }
+                }
+                ElectricPortMode::Transit => {}
+            }
+        }
+
+        nodes
+    }
+
+    // Distance is measured between the closest cells of the footprints.
+    fn is_connected(&self, other: &ElectricNode) -> bool {
+        let distance = footprint_distance(self.position, self.size, other.position, other.size);
+        self.voltage == other.voltage && distance <= self.range.max(other.range)
+    }
+}
+
+pub struct PowerNetwork {
+    pub voltage: u32,
+    pub production: u32,
+    pub consumption: u32,
+    // Positions of all the connected buildings.
+    pub members: Vec<IVec2>,
+}
+
+impl PowerNetwork {
+    // Part of the consumption that is covered by production, in [0, 1].
+    pub fn get_satisfaction(&self) -> f32 {
+        if self.consumption == 0 {
+            1.0
+        } else {
+            (self.production as f32 / self.consumption as f32).min(1.0)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct PowerGrid {
+    networks: Vec<PowerNetwork>,
+    // By the building origin and the voltage of the node.
+    node_networks: HashMap<(IVec2, u32), usize>,
+    consumer_networks: HashMap<IVec2, Vec<usize>>,
+}
+
+impl PowerGrid {
+    // Connections only change with the buildings, energy is updated separately each tick.
+    pub fn new(nodes: &[ElectricNode]) -> PowerGrid {
+        // Union-find over the nodes.
+        let mut parents: Vec<usize> = (0..nodes.len()).collect();
+        fn find(parents: &mut [usize], mut id: usize) -> usize {
+            while parents[id] != id {
+                parents[id] = parents[parents[id]];
+                id = parents[id];
+            }
+            id
+        }
+
+        for a in 0..nodes.len() {
+            for b in (a + 1)..nodes.len() {
+                if nodes[a].is_connected(&nodes[b]) {
+                    let root_a = find(&mut parents, a);
+                    let root_b = find(&mut parents, b);
+                    parents[root_b] = root_a;
+                }
+            }
+        }
+
+        let mut networks = vec![];
+        let mut root_networks = HashMap::new();
+        let mut node_networks = HashMap::new();
+        let mut consumer_networks: HashMap<IVec2, Vec<usize>> = HashMap::new();
+        for (id, node) in nodes.iter().enumerate() {
+            let root = find(&mut parents, id);
+            let network_id = *root_networks.entry(root).or_insert_with(|| {
+                networks.push(PowerNetwork {
+                    voltage: node.voltage,
+                    production: 0,
+                    consumption: 0,
+                    members: vec![],
+                });
+                networks.len() - 1
+            });
+
+            networks[network_id].members.push(node.position);
+            node_networks.insert((node.position, node.voltage), network_id);
+
+            if node.is_consumer {
+                consumer_networks
+                    .entry(node.position)
+                    .or_default()
+                    .push(network_id);
+            }
+        }
+
+        let mut grid = PowerGrid {
+            networks,
+            node_networks,
+            consumer_networks,
+        };
+        grid.update_energy(nodes);
+        grid
+    }
+
+    // Nodes must be the ones the grid was built from, with the energy of the current tick.
+    pub fn update_energy(&mut self, nodes: &[ElectricNode]) {
+        for network in self.networks.iter_mut() {
+            network.production = 0;
+            network.consumption = 0;
+        }
+        for node in nodes {
+            if let Some(&network_id) = self.node_networks.get(&(node.position, node.voltage)) {
+                let network = &mut self.networks[network_id];
+                network.production += node.production;
+                network.consumption += node.consumption;
+            }
+        }
+    }
+
+    pub fn get_networks(&self) -> &[PowerNetwork] {
+        &self.networks
+    }
+
+    pub fn get_network_at(&self, position: IVec2, voltage: u32) -> Option<&PowerNetwork> {
+        self.networks
+            .iter()
+            .find(|network| network.voltage == voltage && network.members.contains(&position))
+    }
+
+    // Lowest satisfaction among the networks building at position consumes from.
+    pub fn get_consumer_satisfaction(&self, position: IVec2) -> Option<f32> {
+        self.consumer_networks.get(&position).map(|network_ids| {
+            network_ids
+                .iter()
+                .map(|&id| self.networks[id].get_satisfaction())
+                .fold(1.0, f32::min)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOLTAGE: u32 = 5;
+
+    fn node(x: isize, production: u32, consumption: u32) -> ElectricNode {
+        ElectricNode {
+            position: IVec2::new(x, 0),
+            size: IVec2::new(1, 1),
+            voltage: VOLTAGE,
+            range: ADJACENT_RANGE,
+            is_consumer: consumption > 0,
+            production,
+            consumption,
+        }
+    }
+
+    #[test]
+    fn consumer_is_satisfied_by_enough_production() {
+        let grid = PowerGrid::new(&[node(0, 10, 0), node(1, 0, 5)]);
+        assert_eq!(grid.get_consumer_satisfaction(IVec2::new(1, 0)), Some(1.0));
+    }
+
+    #[test]
+    fn shortage_is_shared_by_consumers() {
+        let grid = PowerGrid::new(&[node(0, 4, 0), node(1, 0, 6), node(2, 0, 6)]);
+        assert_eq!(grid.get_networks().len(), 1);
+        assert_eq!(
+            grid.get_consumer_satisfaction(IVec2::new(1, 0)),
+            Some(4.0 / 12.0)
+        );
+        assert_eq!(
+            grid.get_consumer_satisfaction(IVec2::new(2, 0)),
+            Some(4.0 / 12.0)
+        );
+    }
+
+    #[test]
+    fn isolated_consumer_gets_nothing() {
+        let grid = PowerGrid::new(&[node(0, 10, 0), node(3, 0, 5)]);
+        assert_eq!(grid.get_networks().len(), 2);
+        assert_eq!(grid.get_consumer_satisfaction(IVec2::new(3, 0)), Some(0.0));
+    }
+
+    #[test]
+    fn energy_update_keeps_connections() {
+        let mut grid = PowerGrid::new(&[node(0, 10, 0), node(1, 0, 5)]);
+        grid.update_energy(&[node(0, 2, 0), node(1, 0, 8)]);
+        assert_eq!(grid.get_consumer_satisfaction(IVec2::new(1, 0)), Some(0.25));
+    }
+}
@@ -0,0 +1,61 @@
+use serde_json::json;
+
+use crate::game::common::{json_reader::JsonReader, json_writer::JsonWriter, math::IVec2};
+
+// Rectangular area of cells given by inclusive "min" and "max",
+// raises an event each time the player enters it.
+pub struct Trigger {
+    name: String,
+    min: IVec2,
+    max: IVec2,
+    player_inside: bool,
+}
+
+impl Trigger {
+    pub fn from_json_object(obj: &serde_json::Value, error: &mut bool) -> Trigger {
+        let name = JsonReader::read_string(obj, "name", error);
+        let min = JsonReader::read_ivec2(obj, "min", error);
+        let max = JsonReader::read_ivec2(obj, "max", error);
+        if min.x > max.x || min.y > max.y {
+            log::error!("Trigger {} has wrong bounds", name);
+            *error = true;
+        }
+
+        Trigger {
+            name,
+            min,
+            max,
+            player_inside: false,
+        }
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    fn contains(&self, cell: IVec2) -> bool {
+        cell.x >= self.min.x && cell.x <= self.max.x && cell.y >= self.min.y && cell.y <= self.max.y
+    }
+
+    // Whether the player has just entered the area.
+    pub fn update_player_cell(&mut self, cell: IVec2) -> bool {
+        let was_inside = self.player_inside;
+        self.player_inside = self.contains(cell);
+        self.player_inside && !was_inside
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        json!({
+            "name": self.name,
+            "min": JsonWriter::write_ivec2(self.min),
+            "max": JsonWriter::write_ivec2(self.max),
+            "player_inside": self.player_inside,
+        })
+    }
+
+    pub fn from_saved_state(state: &serde_json::Value, error: &mut bool) -> Trigger {
+        let mut trigger = Self::from_json_object(state, error);
+        trigger.player_inside = JsonReader::read_bool(state, "player_inside", error);
+        trigger
+    }
+}
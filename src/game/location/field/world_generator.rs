@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use serde_json::json;
+
+use crate::game::{
+    common::{json_reader::JsonReader, math::IVec2, noise::Noise},
+    location::save::LoadContext,
+};
+
+use super::cell::{
+    surface::{Surface, SurfaceFactory},
+    Cell,
+};
+
+// Area where noise exceeds threshold gets the surface.
+#[derive(Clone)]
+struct SurfaceLayer {
+    surface: Surface,
+    // Approximate size of the patches in cells.
+    scale: f32,
+    threshold: f32,
+    // Deposit in the center of the patch, 0 for unlimited surfaces without ore.
+    richness: u32,
+}
+
+impl SurfaceLayer {
+    fn from_json_object(
+        obj: &serde_json::Value,
+        surface_factory: &SurfaceFactory,
+        error: &mut bool,
+    ) -> SurfaceLayer {
+        let surface_name = JsonReader::read_string(obj, "surface", error);
+        SurfaceLayer {
+            surface: surface_factory
+                .create_surface(SurfaceFactory::get_surface_id_by_name(&surface_name)),
+            scale: JsonReader::read_f32(obj, "scale", error).max(1.0),
+            threshold: JsonReader::read_f32(obj, "threshold", error).min(0.99),
+            richness: JsonReader::read_u32(obj, "richness", error),
+        }
+    }
+}
+
+// Fills newly loaded chunks. The same seed always gives the same map.
+#[derive(Clone)]
+pub struct WorldGenerator {
+    // Empty for the generators which aren't from the dictionary.
+    name: String,
+    seed: u32,
+    base_surface: Surface,
+    // Later layers override earlier ones.
+    layers: Vec<SurfaceLayer>,
+}
+
+impl WorldGenerator {
+    // Fills everything with the surface.
+    pub fn flat(surface: Surface) -> WorldGenerator {
+        WorldGenerator {
+            name: String::new(),
+            seed: 0,
+            base_surface: surface,
+            layers: vec![],
+        }
+    }
+
+    fn from_json_object(
+        obj: &serde_json::Value,
+        surface_factory: &SurfaceFactory,
+    ) -> WorldGenerator {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let base_surface_name = JsonReader::read_string(obj, "base_surface", &mut error);
+        let layers = JsonReader::read_vec(obj, "layers", &mut error)
+            .iter()
+            .map(|layer| SurfaceLayer::from_json_object(layer, surface_factory, &mut error))
+            .collect();
+
+        if error {
+            log::error!(
+                "Failed to parse WorldGenerator from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("WorldGenerator succesfully loaded({})", name);
+        }
+
+        WorldGenerator {
+            name,
+            seed: 0,
+            base_surface: surface_factory
+                .create_surface(SurfaceFactory::get_surface_id_by_name(&base_surface_name)),
+            layers,
+        }
+    }
+
+    pub fn get_seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub fn generate_cell(&self, position: IVec2) -> Cell {
+        let mut surface = &self.base_surface;
+        let mut deposit = None;
+
+        for (layer_id, layer) in self.layers.iter().enumerate() {
+            // Each layer has it's own noise.
+            let noise = Noise::new(((self.seed as u64) << 32) | layer_id as u64);
+            let value = noise.sample(position.to_vec2(), layer.scale);
+            if value > layer.threshold {
+                surface = &layer.surface;
+                deposit = if layer.richness > 0 {
+                    // Patches are richer closer to the center.
+                    let intensity = (value - layer.threshold) / (1.0 - layer.threshold);
+                    Some(((layer.richness as f32 * intensity) as u32).max(1))
+                } else {
+                    None
+                };
+            }
+        }
+
+        Cell::with_deposit(surface.clone(), deposit)
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        if self.name.is_empty() {
+            json!({ "surface": self.base_surface.get_name() })
+        } else {
+            json!({ "name": self.name, "seed": self.seed })
+        }
+    }
+
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        context: &LoadContext,
+        error: &mut bool,
+    ) -> WorldGenerator {
+        if state.get("surface").is_some() {
+            let surface_name = JsonReader::read_string(state, "surface", error);
+            return WorldGenerator::flat(
+                context
+                    .surface_factory
+                    .create_surface(SurfaceFactory::get_surface_id_by_name(&surface_name)),
+            );
+        }
+
+        let name = JsonReader::read_string(state, "name", error);
+        let seed = JsonReader::read_u32(state, "seed", error);
+        if !context.world_generator_factory.contains(&name) {
+            *error = true;
+        }
+        context.world_generator_factory.create(&name, seed)
+    }
+}
+
+pub struct WorldGeneratorFactory {
+    generators: HashMap<String, WorldGenerator>,
+}
+
+impl WorldGeneratorFactory {
+    pub fn new(json: Rc<str>, surface_factory: &SurfaceFactory) -> WorldGeneratorFactory {
+        let mut generators = HashMap::new();
+
+        let dictionary = serde_json::from_str(json.as_ref()).unwrap_or_else(|e| {
+            log::error!(
+                "World generator dictionary haven't been succesfully loaded : {}",
+                e
+            );
+            serde_json::Value::Object(serde_json::Map::new())
+        });
+
+        let mut error = false;
+        for obj in JsonReader::read_vec(&dictionary, "world_generators", &mut error) {
+            let generator = WorldGenerator::from_json_object(&obj, surface_factory);
+            generators.insert(generator.name.clone(), generator);
+        }
+
+        if error {
+            log::error!("World generator dictionary haven't been succesfully loaded : wrong JSON file structure");
+        }
+
+        log::info!("{} world generators are loaded", generators.len());
+        WorldGeneratorFactory { generators }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.generators.contains_key(name)
+    }
+
+    pub fn create(&self, name: &str, seed: u32) -> WorldGenerator {
+        match self.generators.get(name) {
+            Some(generator) => WorldGenerator {
+                seed,
+                ..generator.clone()
+            },
+            None => {
+                log::error!("There's no such world generator {}", name);
+                WorldGenerator::flat(Surface::default())
+            }
+        }
+    }
+}
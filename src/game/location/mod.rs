@@ -1,157 +1,304 @@
-use ggez::Context;
-
 use crate::game::common::{
     asset_manager::AssetManager,
-    direction::Direction,
+    json_reader::JsonReader,
     math::{IVec2, Vec2},
 };
 use crate::game::{game_entity::*, gui::with_gui::*, message::*, renderer::Renderer};
+pub mod cursor;
+pub mod event;
 pub mod field;
+pub mod notifications;
 pub mod physics_scene;
 mod player;
+pub mod research;
+pub mod save;
+pub mod scenario;
+pub mod statistics;
 
+use cursor::{Cursor, Hover};
+use event::{EventBus, EventSubscriber, GameEvent};
 use field::{
-    building::{craft_station::CraftStation, item::ItemFactory, transport_belt::TransportBelt},
-    cell::{surface::SurfaceFactory, Cell},
+    building::{footprint_transform, render_item_gui, Building},
+    power::PowerNetwork,
     Field,
 };
+use notifications::Notifications;
 use physics_scene::{BodyCollection, BodyHierarchyRoot, PhysicsSimulated};
+use player::inventory::SLOT_PAYLOAD;
+use research::{Research, Technology};
+use save::LoadContext;
+use scenario::Scenario;
+use statistics::Statistics;
 
 use super::renderer::camera::CameraProperties;
 
+// Max distance(in cells) from the player to the building to inspect.
+const INSPECT_RANGE: f32 = 8.0;
+// Ghost preview opacity.
+const GHOST_ALPHA: f32 = 0.5;
+const BLOCKED_GHOST_ALPHA: f32 = 0.2;
+
 pub struct Location {
     field: Field,
     camera_properties: CameraProperties,
+    // Dictionaries to create buildings and items at runtime.
+    context: LoadContext,
+
+    cursor: Cursor,
+    // Building to place in build mode, rendered as a ghost under the cursor.
+    build_ghost: Option<Box<dyn Building>>,
+
+    research: Research,
+
+    // Emitters only know the bus, the subscribers are registered in create_subscribers.
+    event_bus: EventBus,
+    subscribers: Vec<Box<dyn EventSubscriber>>,
 }
 
 impl Location {
-    pub fn new(asset_manager: &AssetManager) -> Location {
-        let mut field = Field::new(IVec2::new(-5, -5), IVec2::new(5, 5));
-
-        let surface_json = AssetManager::get_asset_id("dictionaries/surfaces.json");
-        let surface_dict = asset_manager.get_json(surface_json);
-        let surface_factory = SurfaceFactory::new(surface_dict);
-        let grass_surface_id = SurfaceFactory::get_surface_id_by_name("grass");
-
-        for cell in &mut field.iter_mut() {
-            let grass_surface = surface_factory.create_surface(grass_surface_id);
-            *cell = Cell::new(grass_surface);
-        }
-
-        let items_dict = AssetManager::get_asset_id("dictionaries/items.json");
-        let item_factory = ItemFactory::new(asset_manager.get_json(items_dict));
-
-        // DEBUG MINER
-        // let json_asset = AssetManager::get_asset_id("dictionaries/miners.json");
-        // let json = asset_manager.get_json(json_asset);
-        // let miners = serde_json::from_str(json.as_ref()).unwrap();
-        // let mut err = false;
-        // let miners = crate::game::common::json_reader::JsonReader::read_vec(&miners, "miners", &mut err);
-        // let mut miner = crate::game::hub::building::miner::Miner::from_json_object(&miners[0]);
-        // miner.init(SurfaceFactory::get_surface_id_by_name("grass"), &item_factory);
-
-        // let cell = field.get_cell_mut(IVec2::new(0, 0)).unwrap();
-        // cell.build(Box::from(miner));
-        // DEBUG RECYCLER
-        let json_asset = AssetManager::get_asset_id("dictionaries/recyclers.json");
-        let json = asset_manager.get_json(json_asset);
-        let recyclers = serde_json::from_str(json.as_ref()).unwrap();
-        let mut err = false;
-        let recyclers = crate::game::common::json_reader::JsonReader::read_vec(
-            &recyclers,
-            "recyclers",
-            &mut err,
-        );
-        let mut recycler = field::building::recycler::Recycler::from_json_object(&recyclers[0]);
-        recycler.init_items(&item_factory);
-
-        let cell = field.get_cell_mut(IVec2::new(1, 1)).unwrap();
-        cell.build(Box::from(recycler), Vec2::new(1.0, 1.0));
-        // DEBUG GENERATOR
-        let json_asset = AssetManager::get_asset_id("dictionaries/recyclers.json");
-        let json = asset_manager.get_json(json_asset);
-        let recyclers = serde_json::from_str(json.as_ref()).unwrap();
-        let mut err = false;
-        let recyclers = crate::game::common::json_reader::JsonReader::read_vec(
-            &recyclers,
-            "recyclers",
-            &mut err,
-        );
-        let mut recycler = field::building::recycler::Recycler::from_json_object(&recyclers[1]);
-        recycler.init_items(&item_factory);
-
-        let cell = field.get_cell_mut(IVec2::new(2, 2)).unwrap();
-        cell.build(Box::from(recycler), Vec2::new(2.0, 2.0));
-        // DEBUG TRANSPORT BELT
-        let json_asset = AssetManager::get_asset_id("dictionaries/transport_belts.json");
-        let json = asset_manager.get_json(json_asset);
-        let tbs = serde_json::from_str(json.as_ref()).unwrap();
-        let mut err = false;
-        let tbs = crate::game::common::json_reader::JsonReader::read_vec(
-            &tbs,
-            "transport_belts",
-            &mut err,
-        );
-        let mut tb = TransportBelt::from_json_object(&tbs[0]);
-        // setup
-        tb.set_config(vec![Direction::Left, Direction::Up], Direction::Right);
-        // setup
-        let cell = field.get_cell_mut(IVec2::new(1, 0)).unwrap();
-        cell.build(Box::from(tb), Vec2::new(1.0, 0.0));
-        // DEBUG TRANSPORT BELT
-        let json_asset = AssetManager::get_asset_id("dictionaries/transport_belts.json");
-        let json = asset_manager.get_json(json_asset);
-        let tbs = serde_json::from_str(json.as_ref()).unwrap();
-        let mut err = false;
-        let tbs = crate::game::common::json_reader::JsonReader::read_vec(
-            &tbs,
-            "transport_belts",
-            &mut err,
-        );
-        let mut tb = TransportBelt::from_json_object(&tbs[0]);
-        // setup
-        tb.set_config(vec![Direction::Left], Direction::Up);
-        // setup
-        let cell = field.get_cell_mut(IVec2::new(2, 0)).unwrap();
-        cell.build(Box::from(tb), Vec2::new(2.0, 0.0));
-        // DEBUG TRANSPORT BELT
-        let json_asset = AssetManager::get_asset_id("dictionaries/transport_belts.json");
-        let json = asset_manager.get_json(json_asset);
-        let tbs = serde_json::from_str(json.as_ref()).unwrap();
-        let mut err = false;
-        let tbs = crate::game::common::json_reader::JsonReader::read_vec(
-            &tbs,
-            "transport_belts",
-            &mut err,
-        );
-        let mut tb = TransportBelt::from_json_object(&tbs[0]);
-        // setup
-        tb.set_config(vec![Direction::Down], Direction::Up);
-        // setup
-        let cell = field.get_cell_mut(IVec2::new(2, 1)).unwrap();
-        cell.build(Box::from(tb), Vec2::new(2.0, 1.0));
-        // DEBUG CRAFT STATION
-        let json_asset = AssetManager::get_asset_id("dictionaries/craft_stations.json");
-        let json = asset_manager.get_json(json_asset);
-        let craft_s = serde_json::from_str(json.as_ref()).unwrap();
-        let craft_s = crate::game::common::json_reader::JsonReader::read_vec(
-            &craft_s,
-            "craft_stations",
-            &mut false,
-        );
-        let craft_station = CraftStation::from_json_object(&craft_s[0], &item_factory);
-        let cell = field.get_cell_mut(IVec2::new(4, 1)).unwrap();
-        cell.build(Box::from(craft_station), Vec2::new(4.0, 1.0));
+    pub fn from_scenario(scenario: &Scenario, asset_manager: &AssetManager) -> Location {
+        let context = LoadContext::new(asset_manager);
+        let mut event_bus = EventBus::default();
+
+        Location {
+            field: scenario.create_field(&context),
+            camera_properties: CameraProperties::default(),
+            context,
+            cursor: Cursor::new(),
+            build_ghost: None,
+            research: Research::default(),
+            subscribers: Self::create_subscribers(&mut event_bus),
+            event_bus,
+        }
+    }
+
+    // The only place to add a system reacting to the gameplay events.
+    fn create_subscribers(event_bus: &mut EventBus) -> Vec<Box<dyn EventSubscriber>> {
+        vec![
+            Box::new(Statistics::new(event_bus)),
+            Box::new(Notifications::new(event_bus)),
+        ]
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        serde_json::json!({
+            "field": self.field.save_state(),
+            "research": self.research.save_state(),
+            "subscribers": self
+                .subscribers
+                .iter()
+                .filter_map(|subscriber| Some((
+                    String::from(subscriber.get_name()),
+                    subscriber.save_state()?,
+                )))
+                .collect::<serde_json::Map<_, _>>(),
+        })
+    }
+
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        asset_manager: &AssetManager,
+        error: &mut bool,
+    ) -> Location {
+        let context = LoadContext::new(asset_manager);
+        let field_state = JsonReader::read_obj(state, "field", error);
+        let research_state = JsonReader::read_obj(state, "research", error);
+        let subscribers_state = JsonReader::read_obj(state, "subscribers", error);
+        let mut event_bus = EventBus::default();
+        let mut subscribers = Self::create_subscribers(&mut event_bus);
+        // Subscribers missing in the save start from scratch.
+        for subscriber in subscribers.iter_mut() {
+            if let Some(subscriber_state) = subscribers_state.get(subscriber.get_name()) {
+                subscriber.load_state(subscriber_state, error);
+            }
+        }
 
         Location {
-            field,
+            field: Field::from_saved_state(&field_state, &context, error),
             camera_properties: CameraProperties::default(),
+            research: Research::from_saved_state(&research_state, &context.technology_tree, error),
+            context,
+            cursor: Cursor::new(),
+            build_ghost: None,
+            subscribers,
+            event_bus,
+        }
+    }
+
+    pub fn set_player_movement_direction(&mut self, direction: Vec2) {
+        self.field.player.set_movement_direction(direction);
+    }
+
+    // Position is in world coordinates.
+    pub fn set_cursor_position(&mut self, position: Vec2) {
+        self.cursor.update(position, &self.field);
+    }
+
+    pub fn get_cursor(&self) -> &Cursor {
+        &self.cursor
+    }
+
+    // Places the building in build mode, selects the hovered one otherwise.
+    pub fn click(&mut self) {
+        if self.build_ghost.is_some() {
+            self.place_building();
+        } else {
+            self.cursor.select_hovered();
+            self.close_distant_inspector();
+        }
+    }
+
+    fn close_distant_inspector(&mut self) {
+        if let Some(origin) = self.cursor.get_selected_building() {
+            let center = Field::get_footprint_center(origin, self.field.get_building_size(origin));
+            let distance = (center - self.field.player.body.get_position()).length();
+            if distance > INSPECT_RANGE {
+                self.cursor.clear_selection();
+            }
+        }
+    }
+
+    // Building is taken from the registry by name, None leaves build mode.
+    pub fn select_building_to_place(&mut self, name: Option<&str>) {
+        if let Some(name) = name {
+            if !self
+                .research
+                .is_building_unlocked(&self.context.technology_tree, name)
+            {
+                log::info!("Building {} isn't researched yet", name);
+                return;
+            }
+        }
+        if name.is_some() {
+            self.cursor.clear_selection();
+        }
+        self.build_ghost = name.map(|name| {
+            let mut building = self.context.building_registry.create(name);
+            building.set_facing(self.field.player.get_build_facing());
+            building
+        });
+    }
+
+    // Places the selected building at the cursor cell, its cost is taken from the player inventory.
+    fn place_building(&mut self) {
+        let building = match &self.build_ghost {
+            Some(ghost) => ghost.clone_box(),
+            None => return,
+        };
+        let position = self.cursor.get_cell_position();
+        let name = String::from(building.get_name());
+
+        let cost = self.context.building_registry.get_cost(&name);
+        if !self.field.player.get_inventory_mut().has_items(cost) {
+            log::info!("Building {} can't be placed : not enough items", name);
+            return;
+        }
+
+        match self.field.place_building(position, building) {
+            Ok(()) => {
+                let inventory = self.field.player.get_inventory_mut();
+                for (item, amount) in cost {
+                    inventory.take_items(item.get_id(), *amount);
+                }
+                self.event_bus.emit(GameEvent::BuildingPlaced {
+                    name,
+                    origin: position,
+                });
+                self.dispatch_events();
+            }
+            Err(e) => log::info!("Building {} can't be placed at {} : {}", name, position, e),
+        }
+    }
+
+    // Cost of the building at the cursor cell is refunded into the player inventory.
+    pub fn deconstruct_building(&mut self) {
+        let position = self.cursor.get_cell_position();
+        let origin = match self.field.get_building_origin(position) {
+            Some(origin) => origin,
+            None => return,
+        };
+        let (name, contents) = match self
+            .field
+            .get_cell(origin)
+            .and_then(|cell| cell.get_building())
+        {
+            Some(building) => (String::from(building.get_name()), building.get_contents()),
+            None => return,
+        };
+
+        // Whatever the building holds goes back along with its cost.
+        let mut refund = self.context.building_registry.get_cost(&name).to_vec();
+        refund.extend(contents);
+        let inventory = self.field.player.get_inventory_mut();
+        if !inventory.can_put_items(&refund) {
+            log::info!(
+                "Building {} can't be deconstructed : inventory is full",
+                name
+            );
+            return;
+        }
+
+        if let Err(e) = self.field.deconstruct(position) {
+            log::error!("Building {} can't be deconstructed : {}", name, e);
+            return;
+        }
+        let inventory = self.field.player.get_inventory_mut();
+        for (item, amount) in refund {
+            inventory.try_put_items(item, amount);
+        }
+
+        self.event_bus
+            .emit(GameEvent::BuildingRemoved { name, origin });
+        self.dispatch_events();
+    }
+
+    // Hands the queued events to the subscribers.
+    fn dispatch_events(&mut self) {
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber.handle_events(&mut self.event_bus);
+        }
+    }
+
+    pub fn take_building_outputs(&mut self) {
+        if let Some(origin) = self.cursor.get_selected_building() {
+            self.field.take_building_outputs(origin);
+        }
+    }
+
+    pub fn put_slot_into_building(&mut self, slot_id: usize) {
+        if let Some(origin) = self.cursor.get_selected_building() {
+            self.field.put_slot_into_building(origin, slot_id);
+        }
+    }
+
+    // Turns the building to place in build mode, otherwise the one in front of the player,
+    // if there is none the next building to place is turned.
+    pub fn rotate_building(&mut self) {
+        let position = self.field.player.get_facing_cell_position();
+        if self.build_ghost.is_some() || self.field.get_building_origin(position).is_none() {
+            self.field.player.rotate_build_facing();
+            if let Some(ghost) = &mut self.build_ghost {
+                ghost.set_facing(self.field.player.get_build_facing());
+            }
+            return;
+        }
+
+        if let Err(e) = self.field.rotate_building(position) {
+            log::info!("Building at {} can't be rotated : {}", position, e);
         }
     }
 
-    // TODO : IT'S DEBUG
-    pub fn process_keyboard_input(&mut self, context: &Context) {
-        self.field.process_keyboard_input(context);
+    // None stops the research, the items paid for the technology are kept.
+    pub fn select_technology(&mut self, name: Option<&str>) {
+        self.research.select(&self.context.technology_tree, name);
+    }
+
+    pub fn get_power_networks(&self) -> &[PowerNetwork] {
+        self.field.get_power_networks()
+    }
+
+    pub fn get_power_satisfaction(&self, position: IVec2) -> Option<f32> {
+        self.field.get_power_satisfaction(position)
     }
 
     pub fn get_camera_properties(&self) -> CameraProperties {
@@ -164,24 +311,48 @@ impl Location {
 impl GameEntity for Location {
     fn update(&mut self, parameters: &UpdateParameters) {
         self.field.update(parameters);
+        self.close_distant_inspector();
     }
 
     fn tick(&mut self, tick_id: u32) {
+        let tree = &self.context.technology_tree;
+        self.field.set_research(
+            &self.research.get_demand(tree),
+            &self.research.get_locked_recipes(tree),
+        );
         self.field.tick(tick_id);
 
-        let mut messages = self.field.pull_messages(tick_id);
-        loop {
-            let msg = match messages.pop() {
-                Some(msg) => msg,
-                None => break,
-            };
-            let msg = self.field.try_push_message(msg);
-            messages.append(&mut msg.into_iter().collect());
+        // Messages are routed one at a time in the order the field gives them. Message that
+        // isn't accepted tries the rest of its directions and then goes back to the sender
+        // as a refund, all before the next message is routed.
+        for msg in self.field.pull_messages(tick_id) {
+            let mut pending = Some(msg);
+            while let Some(msg) = pending {
+                pending = self.field.try_push_message(msg);
+            }
+        }
+
+        let researching = self.research.get_current().map(String::from);
+        for (item, amount) in self.field.take_research_items() {
+            self.research
+                .pay(&self.context.technology_tree, &item, amount);
+        }
+
+        for event in self.field.take_events() {
+            self.event_bus.emit(event);
+        }
+        if let Some(name) = researching {
+            if self.research.is_researched(&name) {
+                self.event_bus
+                    .emit(GameEvent::TechnologyResearched { name });
+            }
         }
+        self.dispatch_events();
     }
 
     fn render(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
         self.field.render(renderer, transform.clone());
+        self.render_build_ghost(renderer, transform);
     }
 }
 
@@ -200,8 +371,285 @@ impl PhysicsSimulated for Location {
     }
 }
 
+impl Location {
+    fn render_build_ghost(&mut self, renderer: &mut Renderer, transform: SpriteTransform) {
+        let size = match &self.build_ghost {
+            Some(ghost) => ghost.get_size(),
+            None => return,
+        };
+        let position = self.cursor.get_cell_position();
+        let alpha = match self.field.check_placement(position, size) {
+            Ok(()) => GHOST_ALPHA,
+            Err(_) => BLOCKED_GHOST_ALPHA,
+        };
+
+        let ghost_transform = SpriteTransform::default()
+            .add_translation(position.to_vec2())
+            .add_alpha(alpha)
+            .combine(&footprint_transform(size));
+        if let Some(ghost) = &mut self.build_ghost {
+            ghost.render(renderer, transform.combine(&ghost_transform));
+        }
+    }
+
+    fn render_cursor_info(&self, params: &mut GuiRenderParams) {
+        imgui::Window::new("cursor")
+            .size([200.0, 80.0], imgui::Condition::FirstUseEver)
+            .position([0.0, params.screen_size.y], imgui::Condition::FirstUseEver)
+            .position_pivot([0.0, 1.0])
+            .build(&params.ui, || {
+                let position = self.cursor.get_cell_position();
+                params.ui.text(format!("cell: {}", position));
+                if let Some(cell) = self.field.get_cell(position) {
+                    params
+                        .ui
+                        .text(format!("surface: {}", cell.get_surface().get_name()));
+                    if let Some(deposit) = cell.get_deposit() {
+                        params.ui.text(format!("deposit: {}", deposit));
+                    }
+                }
+
+                match self.cursor.get_hover() {
+                    Some(Hover::Building(origin)) => {
+                        if let Some(building) = self
+                            .field
+                            .get_cell(origin)
+                            .and_then(|cell| cell.get_building())
+                        {
+                            params.ui.text(format!("building: {}", building.get_name()));
+                        }
+                    }
+                    Some(Hover::LayingObject(position)) => {
+                        if let Some(obj) = self.field.get_laying_object_at(position) {
+                            params.ui.text(format!(
+                                "items: {} x{}",
+                                obj.get_item().get_name(),
+                                obj.get_amount()
+                            ));
+                        }
+                    }
+                    _ => {}
+                }
+            });
+    }
+
+    // Common power state and the building's own gui.
+    fn render_inspector(&mut self, params: &mut GuiRenderParams) {
+        let origin = match self.cursor.get_selected_building() {
+            Some(origin) => origin,
+            None => return,
+        };
+        let field = &mut self.field;
+        let name = match field.get_cell(origin).and_then(|cell| cell.get_building()) {
+            Some(building) => String::from(building.get_name()),
+            None => return,
+        };
+
+        let mut opened = true;
+        let mut take_outputs = false;
+        let mut dropped_slot = None;
+        imgui::Window::new(format!("{}###inspector", name))
+            .size([300.0, 250.0], imgui::Condition::FirstUseEver)
+            .position(
+                [params.screen_size.x, 120.0],
+                imgui::Condition::FirstUseEver,
+            )
+            .position_pivot([1.0, 0.0])
+            .opened(&mut opened)
+            .build(&params.ui, || {
+                Self::render_power_state(field, origin, params);
+                if let Some(cell) = field.get_cell_mut(origin) {
+                    cell.render_gui(params);
+                }
+
+                params.ui.separator();
+                take_outputs = params.ui.button("take outputs");
+                params.ui.same_line();
+                params.ui.button("drop items here");
+                if let Some(target) = imgui::DragDropTarget::new(params.ui) {
+                    if let Some(Ok(payload)) = target
+                        .accept_payload::<usize, _>(SLOT_PAYLOAD, imgui::DragDropFlags::empty())
+                    {
+                        dropped_slot = Some(payload.data);
+                    }
+                    target.pop();
+                }
+            });
+
+        if take_outputs {
+            self.take_building_outputs();
+        }
+        if let Some(slot_id) = dropped_slot {
+            self.put_slot_into_building(slot_id);
+        }
+        if !opened {
+            self.cursor.clear_selection();
+        }
+    }
+
+    fn render_power_state(field: &Field, origin: IVec2, params: &mut GuiRenderParams) {
+        let building = field.get_cell(origin).unwrap().get_building().unwrap();
+        let mut voltages: Vec<u32> = building
+            .get_electric_ports()
+            .iter()
+            .map(|port| port.voltage)
+            .collect();
+        voltages.sort_unstable();
+        voltages.dedup();
+        if voltages.is_empty() {
+            return;
+        }
+
+        if let Some(satisfaction) = field.get_power_satisfaction(origin) {
+            params
+                .ui
+                .text(format!("power: {:.0}%", satisfaction * 100.0));
+        }
+        for voltage in voltages {
+            match field.get_power_network_at(origin, voltage) {
+                Some(network) => params.ui.text(format!(
+                    "{}V network: {}/{}",
+                    voltage, network.production, network.consumption
+                )),
+                None => params.ui.text(format!("{}V: not connected", voltage)),
+            }
+        }
+        params.ui.separator();
+    }
+
+    fn render_build_menu(&mut self, params: &mut GuiRenderParams) {
+        let mut selected = None;
+        let current = self
+            .build_ghost
+            .as_ref()
+            .map(|ghost| String::from(ghost.get_name()));
+
+        imgui::Window::new("build")
+            .size([200.0, 250.0], imgui::Condition::FirstUseEver)
+            .position([0.0, 0.0], imgui::Condition::FirstUseEver)
+            .build(&params.ui, || {
+                for name in self.context.building_registry.get_names() {
+                    if !self
+                        .research
+                        .is_building_unlocked(&self.context.technology_tree, name)
+                    {
+                        continue;
+                    }
+                    let is_current = current.as_deref() == Some(name.as_str());
+                    if imgui::Selectable::new(name)
+                        .selected(is_current)
+                        .build(params.ui)
+                    {
+                        selected = Some(if is_current { None } else { Some(name.clone()) });
+                    }
+                }
+                params.ui.separator();
+                params.ui.text("LMB place, RMB deconstruct");
+                params.ui.text("R rotate, Q stop building");
+            });
+
+        if let Some(selected) = selected {
+            self.select_building_to_place(selected.as_deref());
+        }
+    }
+
+    // Technologies are nested under their prerequisites.
+    fn render_research_window(&mut self, params: &mut GuiRenderParams) {
+        let mut selected = None;
+        let tree = &self.context.technology_tree;
+
+        imgui::Window::new("research")
+            .size([300.0, 300.0], imgui::Condition::FirstUseEver)
+            .position([0.0, 260.0], imgui::Condition::FirstUseEver)
+            .build(&params.ui, || {
+                match self.research.get_current() {
+                    Some(current) => {
+                        params.ui.text(format!("researching: {}", current));
+                        if params.ui.button("stop") {
+                            selected = Some(None);
+                        }
+                    }
+                    None => params.ui.text("researching: -"),
+                }
+                params.ui.separator();
+
+                for technology in tree.get_technologies() {
+                    if technology.get_prerequisites().is_empty() {
+                        self.render_technology_gui(technology, params, &mut selected);
+                    }
+                }
+            });
+
+        if let Some(selected) = selected {
+            self.select_technology(selected.as_deref());
+        }
+    }
+
+    fn render_technology_gui(
+        &self,
+        technology: &Technology,
+        params: &mut GuiRenderParams,
+        selected: &mut Option<Option<String>>,
+    ) {
+        let tree = &self.context.technology_tree;
+        let node = imgui::TreeNode::new(technology.get_name()).push(params.ui);
+
+        params.ui.same_line();
+        if self.research.is_researched(technology.get_name()) {
+            params.ui.text_disabled("researched");
+        } else if self.research.get_current() == Some(technology.get_name()) {
+            params.ui.text("in progress");
+        } else if self.research.can_research(technology) {
+            if params
+                .ui
+                .small_button(format!("research##{}", technology.get_name()))
+            {
+                *selected = Some(Some(String::from(technology.get_name())));
+            }
+        } else {
+            params.ui.text_disabled("locked");
+        }
+
+        if let Some(node) = node {
+            if !self.research.is_researched(technology.get_name()) {
+                let paid = self.research.get_paid(technology);
+                for ((item, amount), paid) in technology.get_cost().iter().zip(paid) {
+                    render_item_gui(params, item, &format!("{}/{}", paid, amount));
+                }
+            }
+            let unlocks: Vec<&str> = technology
+                .get_buildings()
+                .iter()
+                .chain(technology.get_recipes())
+                .map(|name| name.as_str())
+                .collect();
+            if !unlocks.is_empty() {
+                params.ui.text(format!("unlocks: {}", unlocks.join(", ")));
+            }
+
+            for child in tree.get_technologies() {
+                if child
+                    .get_prerequisites()
+                    .iter()
+                    .any(|prerequisite| prerequisite == technology.get_name())
+                {
+                    self.render_technology_gui(child, params, selected);
+                }
+            }
+            node.pop();
+        }
+    }
+}
+
 impl WithGui for Location {
     fn render_gui(&mut self, params: &mut GuiRenderParams) {
         self.field.render_gui(params);
+        self.render_build_menu(params);
+        self.render_research_window(params);
+        self.render_cursor_info(params);
+        self.render_inspector(params);
+        for subscriber in self.subscribers.iter_mut() {
+            subscriber.render_gui(params);
+        }
     }
 }
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+
+use crate::game::gui::with_gui::*;
+
+use super::event::{EventBus, EventSubscriber, GameEventKind, SubscriberId};
+
+// Older notifications are dropped.
+const MAX_NOTIFICATIONS: usize = 8;
+
+// Latest events worth the player's attention, not saved.
+pub struct Notifications {
+    subscriber: SubscriberId,
+    // Newest last.
+    lines: VecDeque<String>,
+}
+
+impl Notifications {
+    pub fn new(event_bus: &mut EventBus) -> Notifications {
+        Notifications {
+            subscriber: event_bus.subscribe(&[
+                GameEventKind::PowerShortage,
+                GameEventKind::PlayerEnteredTrigger,
+                GameEventKind::TechnologyResearched,
+            ]),
+            lines: VecDeque::new(),
+        }
+    }
+}
+
+impl EventSubscriber for Notifications {
+    fn get_name(&self) -> &str {
+        "notifications"
+    }
+
+    fn handle_events(&mut self, event_bus: &mut EventBus) {
+        for event in event_bus.take_events(self.subscriber) {
+            self.lines.push_back(event.get_description());
+            if self.lines.len() > MAX_NOTIFICATIONS {
+                self.lines.pop_front();
+            }
+        }
+    }
+}
+
+impl WithGui for Notifications {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        if self.lines.is_empty() {
+            return;
+        }
+
+        let mut opened = true;
+        imgui::Window::new("notifications")
+            .size([300.0, 150.0], imgui::Condition::FirstUseEver)
+            .position(
+                [params.screen_size.x, params.screen_size.y],
+                imgui::Condition::FirstUseEver,
+            )
+            .position_pivot([1.0, 1.0])
+            .opened(&mut opened)
+            .build(&params.ui, || {
+                for line in &self.lines {
+                    params.ui.text(line);
+                }
+            });
+
+        // Closing the window dismisses the notifications.
+        if !opened {
+            self.lines.clear();
+        }
+    }
+}
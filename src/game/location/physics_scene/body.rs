@@ -101,6 +101,18 @@ impl Body {
         self.position = position;
     }
 
+    pub fn get_velocity(&self) -> Vec2 {
+        self.velocity
+    }
+
+    pub fn set_velocity(&mut self, velocity: Vec2) {
+        self.velocity = velocity;
+    }
+
+    pub fn get_mass(&self) -> f32 {
+        self.mass
+    }
+
     pub fn set_position_kinematic(&mut self, position: Vec2, delta_time: f32) {
         self.velocity = (position - self.position) / delta_time;
         self.position = position;
@@ -8,7 +8,7 @@ pub mod collision_data;
 pub mod message;
 mod physics_simulated;
 
-pub use body::Body;
+pub use body::{Body, BodyId};
 pub use collider::{Collider, ColliderShape};
 pub use physics_simulated::PhysicsSimulated;
 
@@ -211,12 +211,14 @@ fn resolve_collision(
     let collision_data = a.collider.collide(&b.collider);
     match collision_data {
         Some(data) => {
+            let is_trigger = a.collider.is_trigger || b.collider.is_trigger;
             let bouncity = Math::min(a.bouncity, b.bouncity);
 
             let relative_velocity = b.velocity - a.velocity;
             let velocity_along_normal = relative_velocity.dot(data.normal);
 
-            if velocity_along_normal < 0.0 {
+            // Triggers only report the contact, they don't push anything.
+            if velocity_along_normal < 0.0 && !is_trigger {
                 let inv_sum = a.inv_mass + b.inv_mass;
                 let impulse = -1.0 * (1.0 + bouncity) * velocity_along_normal / inv_sum;
                 let impulse_vec = data.normal * impulse;
@@ -240,7 +242,7 @@ fn resolve_collision(
                 }
             }
 
-            if !a.collider.is_trigger && !b.collider.is_trigger {
+            if !is_trigger {
                 let mut reversed_data = data.clone();
                 reversed_data.reverse();
                 vec![
@@ -1,6 +1,16 @@
-use crate::game::{gui::with_gui::*, location::field::building::item::Item};
+use serde_json::json;
 
-#[derive(Default)]
+use crate::game::{
+    common::json_reader::JsonReader,
+    gui::with_gui::*,
+    location::field::building::item::{Item, ItemFactory, ItemId},
+};
+
+// Drag and drop payload type, the payload is a slot id.
+pub const SLOT_PAYLOAD: &str = "inventory slot";
+const SLOTS_PER_ROW: usize = 8;
+
+#[derive(Default, Clone)]
 enum Slot {
     #[default]
     Empty,
@@ -10,9 +20,12 @@ enum Slot {
     },
 }
 
+#[derive(Clone)]
 pub struct Inventory {
     slots: Vec<Slot>,
     slot_capacity: usize,
+    // Slot dragged to the drop button, handled by the owner as it needs the field.
+    drop_request: Option<usize>,
 }
 
 impl Inventory {
@@ -23,6 +36,241 @@ impl Inventory {
                 .take(slot_amount)
                 .collect(),
             slot_capacity,
+            drop_request: None,
+        }
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        serde_json::Value::Array(
+            self.slots
+                .iter()
+                .map(|slot| match slot {
+                    Slot::Empty => serde_json::Value::Null,
+                    Slot::Filled { item, amount } => {
+                        json!({ "item": item.get_name(), "amount": amount })
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    pub fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        let saved_slots = match state {
+            serde_json::Value::Array(saved_slots) if saved_slots.len() == self.slots.len() => {
+                saved_slots
+            }
+            _ => {
+                *error = true;
+                return;
+            }
+        };
+
+        for (slot, saved_slot) in self.slots.iter_mut().zip(saved_slots) {
+            *slot = match saved_slot {
+                serde_json::Value::Null => Slot::Empty,
+                saved_slot => {
+                    let item_name = JsonReader::read_string(saved_slot, "item", error);
+                    Slot::Filled {
+                        item: item_factory
+                            .create_item(ItemFactory::get_item_id_by_name(&item_name)),
+                        amount: JsonReader::read_u32(saved_slot, "amount", error) as usize,
+                    }
+                }
+            };
+        }
+    }
+
+    pub fn take_drop_request(&mut self) -> Option<usize> {
+        self.drop_request.take()
+    }
+
+    // Whether all the stacks fit at once.
+    pub fn can_put_items(&self, items: &[(Item, usize)]) -> bool {
+        let mut inventory = self.clone();
+        items
+            .iter()
+            .all(|(item, amount)| inventory.try_put_items(item.clone(), *amount).is_none())
+    }
+
+    // Whether all the stacks can be taken at once.
+    pub fn has_items(&self, items: &[(Item, usize)]) -> bool {
+        items
+            .iter()
+            .all(|(item, amount)| self.count_items(item.get_id()) >= *amount)
+    }
+
+    pub fn get_slot(&self, slot_id: usize) -> Option<(&Item, usize)> {
+        match self.slots.get(slot_id) {
+            Some(Slot::Filled { item, amount }) => Some((item, *amount)),
+            _ => None,
+        }
+    }
+
+    pub fn get_first_filled_slot(&self) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| matches!(slot, Slot::Filled { .. }))
+    }
+
+    // Stacks of the same items merged, in the order of the slots.
+    pub fn get_item_totals(&self) -> Vec<(Item, usize)> {
+        let mut totals: Vec<(Item, usize)> = vec![];
+        for slot in &self.slots {
+            if let Slot::Filled { item, amount } = slot {
+                match totals
+                    .iter_mut()
+                    .find(|(other, _)| other.get_id() == item.get_id())
+                {
+                    Some((_, total)) => *total += amount,
+                    None => totals.push((item.clone(), *amount)),
+                }
+            }
+        }
+        totals
+    }
+
+    pub fn count_items(&self, item_id: ItemId) -> usize {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Filled { item, amount } if item.get_id() == item_id => *amount,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    // Amount of the item that can be put.
+    pub fn get_free_space(&self, item_id: ItemId) -> usize {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                Slot::Empty => self.slot_capacity,
+                Slot::Filled { item, amount } if item.get_id() == item_id => {
+                    self.slot_capacity - *amount
+                }
+                _ => 0,
+            })
+            .sum()
+    }
+
+    // Takes up to amount of the item starting from the last slots,
+    // returns the taken items if there were any.
+    pub fn take_items(&mut self, item_id: ItemId, amount: usize) -> Option<(Item, usize)> {
+        let mut taken: Option<(Item, usize)> = None;
+        for slot_id in (0..self.slots.len()).rev() {
+            let taken_amount = taken.as_ref().map_or(0, |(_, taken_amount)| *taken_amount);
+            if taken_amount == amount {
+                break;
+            }
+
+            if matches!(&self.slots[slot_id], Slot::Filled { item, .. } if item.get_id() == item_id)
+            {
+                let (item, slot_taken) = self.take_from_slot(slot_id, amount - taken_amount)?;
+                taken = Some((item, taken_amount + slot_taken));
+            }
+        }
+        taken
+    }
+
+    // All or nothing version of take_items.
+    pub fn try_take_items(&mut self, item_id: ItemId, amount: usize) -> Option<(Item, usize)> {
+        if self.count_items(item_id) < amount {
+            return None;
+        }
+        self.take_items(item_id, amount)
+    }
+
+    pub fn take_from_slot(&mut self, slot_id: usize, amount: usize) -> Option<(Item, usize)> {
+        let slot = self.slots.get_mut(slot_id)?;
+        let (taken_item, taken_amount) = match slot {
+            Slot::Filled {
+                item,
+                amount: slot_amount,
+            } => {
+                let taken_amount = amount.min(*slot_amount);
+                *slot_amount -= taken_amount;
+                (item.clone(), taken_amount)
+            }
+            Slot::Empty => return None,
+        };
+
+        if matches!(slot, Slot::Filled { amount: 0, .. }) {
+            *slot = Slot::Empty;
+        }
+        Some((taken_item, taken_amount))
+    }
+
+    // Merges into the same items, swaps with the different ones.
+    pub fn move_slot(&mut self, from: usize, to: usize) {
+        if from == to || from >= self.slots.len() || to >= self.slots.len() {
+            return;
+        }
+
+        let same_items = match (&self.slots[from], &self.slots[to]) {
+            (
+                Slot::Filled {
+                    item: from_item, ..
+                },
+                Slot::Filled { item: to_item, .. },
+            ) => from_item.get_id() == to_item.get_id(),
+            _ => false,
+        };
+        if !same_items {
+            self.slots.swap(from, to);
+            return;
+        }
+
+        let moved = match (&self.slots[from], &self.slots[to]) {
+            (
+                Slot::Filled {
+                    amount: from_amount,
+                    ..
+                },
+                Slot::Filled {
+                    amount: to_amount, ..
+                },
+            ) => (*from_amount).min(self.slot_capacity - *to_amount),
+            _ => 0,
+        };
+        self.take_from_slot(from, moved);
+        if let Slot::Filled { amount, .. } = &mut self.slots[to] {
+            *amount += moved;
+        }
+    }
+
+    // Half of the stack goes to the first empty slot.
+    pub fn split_slot(&mut self, slot_id: usize) {
+        let half = match self.slots.get(slot_id) {
+            Some(Slot::Filled { amount, .. }) if *amount > 1 => amount / 2,
+            _ => return,
+        };
+        let empty_id = match self
+            .slots
+            .iter()
+            .position(|slot| matches!(slot, Slot::Empty))
+        {
+            Some(id) => id,
+            None => return,
+        };
+
+        if let Some((item, amount)) = self.take_from_slot(slot_id, half) {
+            self.slots[empty_id] = Slot::Filled { item, amount };
+        }
+    }
+
+    // Merges stacks of the same items and orders them by item name.
+    pub fn sort(&mut self) {
+        let mut items = self.get_item_totals();
+        items.sort_by(|a, b| a.0.get_name().cmp(b.0.get_name()));
+
+        self.slots = vec![Slot::Empty; self.slots.len()];
+        for (item, amount) in items {
+            self.try_put_items(item, amount);
         }
     }
 
@@ -65,17 +313,73 @@ impl Inventory {
     }
 }
 
+impl Inventory {
+    // Slot grid in the current window, slots are dragged within the same payload type
+    // to move stacks, right click splits a stack.
+    pub fn render_slots(&mut self, params: &mut GuiRenderParams, payload_type: &str) {
+        let mut moved = None;
+        let mut split = None;
+
+        params
+            .ui
+            .columns(self.slots.len().min(SLOTS_PER_ROW) as i32, "slots", false);
+        for (slot_id, slot) in self.slots.iter().enumerate() {
+            let id_token = params.ui.push_id(slot_id as i32);
+            match slot {
+                Slot::Filled { item, amount } => {
+                    imgui::ImageButton::new(
+                        params.get_or_load_texture_id(item.get_sprite_asset_id()),
+                        [20.0, 20.0],
+                    )
+                    .build(params.ui);
+                    if let Some(tooltip) =
+                        imgui::DragDropSource::new(payload_type).begin_payload(params.ui, slot_id)
+                    {
+                        params.ui.text(format!("{} x{}", item.get_name(), amount));
+                        tooltip.end();
+                    }
+                    if params.ui.is_item_hovered()
+                        && params.ui.is_mouse_clicked(imgui::MouseButton::Right)
+                    {
+                        split = Some(slot_id);
+                    }
+                    params.ui.text(format!("x{}", amount));
+                }
+                Slot::Empty => {
+                    params.ui.invisible_button("", [20.0, 20.0]);
+                }
+            }
+
+            if let Some(target) = imgui::DragDropTarget::new(params.ui) {
+                if let Some(Ok(payload)) =
+                    target.accept_payload::<usize, _>(payload_type, imgui::DragDropFlags::empty())
+                {
+                    moved = Some((payload.data, slot_id));
+                }
+                target.pop();
+            }
+
+            id_token.pop();
+            params.ui.next_column();
+        }
+        params.ui.columns(1, "slots end", false);
+
+        if let Some((from, to)) = moved {
+            self.move_slot(from, to);
+        }
+        if let Some(slot_id) = split {
+            self.split_slot(slot_id);
+        }
+    }
+}
+
 impl WithGui for Inventory {
     fn render_gui(&mut self, params: &mut GuiRenderParams) {
-        for i in 0..self.slots.len() {
-            self.slots[i] = Slot::Filled {
-                item: Item::new_error(),
-                amount: i * 10,
-            };
-        }
+        let mut sort = false;
+        let mut drop_request = None;
 
         imgui::Window::new("inventory")
-            .size([320.0, 50.0], imgui::Condition::Always)
+            .size([320.0, 75.0], imgui::Condition::Always)
             .no_decoration()
             .position(
                 [params.screen_size.x * 0.5, params.screen_size.y],
@@ -83,25 +387,26 @@ impl WithGui for Inventory {
             )
             .position_pivot([0.5, 1.0])
             .build(&params.ui, || {
-                params.ui.columns(self.slots.len() as i32, "slots", false);
-
-                for slot in &self.slots {
-                    match slot {
-                        Slot::Filled { item, amount } => {
-                            imgui::Image::new(
-                                params.get_or_load_texture_id(item.get_sprite_asset_id()),
-                                [20.0, 20.0],
-                            )
-                            .build(params.ui);
-                            params.ui.text(format!("x{}", amount));
-                        }
-                        Slot::Empty => {
-                            params.ui.invisible_button("", [20.0, 20.0]);
-                        }
-                    }
+                self.render_slots(params, SLOT_PAYLOAD);
 
-                    params.ui.next_column();
+                sort = params.ui.small_button("sort");
+                params.ui.same_line();
+                params.ui.small_button("drop");
+                if let Some(target) = imgui::DragDropTarget::new(params.ui) {
+                    if let Some(Ok(payload)) = target
+                        .accept_payload::<usize, _>(SLOT_PAYLOAD, imgui::DragDropFlags::empty())
+                    {
+                        drop_request = Some(payload.data);
+                    }
+                    target.pop();
                 }
             });
+
+        if sort {
+            self.sort();
+        }
+        if drop_request.is_some() {
+            self.drop_request = drop_request;
+        }
     }
 }
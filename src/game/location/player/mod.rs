@@ -1,18 +1,22 @@
-use ggez::event::KeyCode;
-use ggez::input::keyboard;
-use ggez::Context;
+use serde_json::json;
 
 use crate::game::common::asset_manager::AssetManager;
-use crate::game::common::math::{Math, Vec2};
+use crate::game::common::{
+    direction::Direction,
+    json_reader::JsonReader,
+    json_writer::JsonWriter,
+    math::{IVec2, Math, Vec2},
+};
 use crate::game::game_entity::*;
 use crate::game::gui::with_gui::*;
+use crate::game::location::field::building::item::ItemFactory;
 use crate::game::renderer::Sprite;
 
 use crate::game::location::physics_scene::{
     message as physics_message, message::MessageBody as PhysicsMessageBody, BodyCollection, *,
 };
 
-mod inventory;
+pub mod inventory;
 use inventory::Inventory;
 
 pub struct Player {
@@ -25,6 +29,10 @@ pub struct Player {
 
     velocity: Vec2,
     direction: Vec2,
+    // Last movement direction along the dominant axis.
+    facing: Direction,
+    // Facing of the buildings placed by the player.
+    build_facing: Direction,
 
     inventory: Inventory,
 }
@@ -52,28 +60,80 @@ impl Player {
 
             velocity: Vec2::zero(),
             direction: Vec2::zero(),
+            facing: Direction::Up,
+            build_facing: Direction::Up,
 
             inventory: Inventory::new(8, 16),
         }
     }
 
-    pub fn process_keyboard_input(&mut self, context: &Context) {
-        let mut dir = Vec2::zero();
+    pub fn set_movement_direction(&mut self, direction: Vec2) {
+        self.direction = direction;
 
-        if keyboard::is_key_pressed(context, KeyCode::A) {
-            dir = dir + Vec2::new(-1.0, 0.0);
-        }
-        if keyboard::is_key_pressed(context, KeyCode::D) {
-            dir = dir + Vec2::new(1.0, 0.0);
-        }
-        if keyboard::is_key_pressed(context, KeyCode::W) {
-            dir = dir + Vec2::new(0.0, 1.0);
-        }
-        if keyboard::is_key_pressed(context, KeyCode::S) {
-            dir = dir + Vec2::new(0.0, -1.0);
+        if !Math::small_enought(direction.sqr_length()) {
+            self.facing = if f32::abs(direction.x) > f32::abs(direction.y) {
+                Direction::from_ivec2(IVec2::new(direction.x.signum() as isize, 0))
+            } else {
+                Direction::from_ivec2(IVec2::new(0, direction.y.signum() as isize))
+            };
         }
+    }
+
+    pub fn get_cell_position(&self) -> IVec2 {
+        self.body.get_position().to_ivec2_rounded()
+    }
+
+    // Cell next to the player in the facing direction.
+    pub fn get_facing_cell_position(&self) -> IVec2 {
+        self.get_cell_position() + self.facing.to_ivec2()
+    }
+
+    pub fn get_inventory_mut(&mut self) -> &mut Inventory {
+        &mut self.inventory
+    }
 
-        self.direction = dir;
+    pub fn get_build_facing(&self) -> Direction {
+        self.build_facing
+    }
+
+    pub fn rotate_build_facing(&mut self) {
+        self.build_facing = self.build_facing.rotate_clockwise();
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        json!({
+            "position": JsonWriter::write_vec2(self.body.get_position()),
+            "velocity": JsonWriter::write_vec2(self.velocity),
+            "facing": self.facing.get_name(),
+            "build_facing": self.build_facing.get_name(),
+            "inventory": self.inventory.save_state(),
+        })
+    }
+
+    pub fn load_state(
+        &mut self,
+        state: &serde_json::Value,
+        item_factory: &ItemFactory,
+        error: &mut bool,
+    ) {
+        self.body
+            .set_position(JsonReader::read_vec2(state, "position", error));
+        self.velocity = JsonReader::read_vec2(state, "velocity", error);
+        let mut read_facing = |name: &str| {
+            Direction::from_name(&JsonReader::read_string(state, name, error)).unwrap_or_else(
+                || {
+                    *error = true;
+                    Direction::Up
+                },
+            )
+        };
+        self.facing = read_facing("facing");
+        self.build_facing = read_facing("build_facing");
+
+        match state.get("inventory") {
+            Some(inventory) => self.inventory.load_state(inventory, item_factory, error),
+            None => *error = true,
+        }
     }
 
     fn apply_movement(&mut self, delta_time: f32) {
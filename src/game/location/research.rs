@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use serde_json::json;
+
+use crate::game::common::{asset_manager::AssetManager, json_reader::JsonReader};
+use crate::game::location::field::building::{
+    building_registry::BuildingRegistry,
+    item::{Item, ItemFactory},
+};
+
+pub struct Technology {
+    name: String,
+    prerequisites: Vec<String>,
+    // Paid in items consumed by the labs.
+    cost: Vec<(Item, usize)>,
+    buildings: Vec<String>,
+    recipes: Vec<String>,
+}
+
+impl Technology {
+    fn from_json_object(obj: &serde_json::Value, item_factory: &ItemFactory) -> Technology {
+        let mut error = false;
+
+        let name = JsonReader::read_string(obj, "name", &mut error);
+        let prerequisites = Self::read_names(obj, "prerequisites", &mut error);
+        let cost = JsonReader::read_vec(obj, "cost", &mut error)
+            .iter()
+            .map(|item| {
+                let item_name = JsonReader::read_string(item, "item", &mut error);
+                let amount = JsonReader::read_u32(item, "amount", &mut error) as usize;
+                (
+                    item_factory.create_item(ItemFactory::get_item_id_by_name(&item_name)),
+                    amount,
+                )
+            })
+            .collect();
+        // Optional "buildings" and "recipes".
+        let unlocks = JsonReader::read_obj(obj, "unlocks", &mut error);
+        let mut read_unlocks = |name: &str| match unlocks.get(name) {
+            Some(_) => Self::read_names(&unlocks, name, &mut error),
+            None => vec![],
+        };
+        let buildings = read_unlocks("buildings");
+        let recipes = read_unlocks("recipes");
+
+        if error {
+            log::error!(
+                "Failed to parse Technology from json ({})",
+                if name.is_empty() {
+                    "error loading name"
+                } else {
+                    &name
+                }
+            );
+        } else {
+            log::info!("Technology succesfully loaded({})", name);
+        }
+
+        Technology {
+            name,
+            prerequisites,
+            cost,
+            buildings,
+            recipes,
+        }
+    }
+
+    fn read_names(obj: &serde_json::Value, name: &str, error: &mut bool) -> Vec<String> {
+        JsonReader::read_vec(obj, name, error)
+            .iter()
+            .filter_map(|value| {
+                let value = value.as_str().map(String::from);
+                if value.is_none() {
+                    *error = true;
+                }
+                value
+            })
+            .collect()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_prerequisites(&self) -> &[String] {
+        &self.prerequisites
+    }
+
+    pub fn get_cost(&self) -> &[(Item, usize)] {
+        &self.cost
+    }
+
+    pub fn get_buildings(&self) -> &[String] {
+        &self.buildings
+    }
+
+    pub fn get_recipes(&self) -> &[String] {
+        &self.recipes
+    }
+}
+
+// Technologies from technologies.json, in order of declaration.
+pub struct TechnologyTree {
+    technologies: Vec<Technology>,
+}
+
+impl TechnologyTree {
+    pub fn new(
+        asset_manager: &AssetManager,
+        item_factory: &ItemFactory,
+        building_registry: &BuildingRegistry,
+    ) -> TechnologyTree {
+        let json =
+            asset_manager.get_json(AssetManager::get_asset_id("dictionaries/technologies.json"));
+        let dictionary = serde_json::from_str(json.as_ref()).unwrap_or_else(|e| {
+            log::error!(
+                "Technology dictionary haven't been succesfully loaded : {}",
+                e
+            );
+            serde_json::Value::Null
+        });
+
+        let mut error = false;
+        let objects = JsonReader::read_vec(&dictionary, "technologies", &mut error);
+        if error {
+            log::error!(
+                "Technology dictionary haven't been succesfully loaded : wrong JSON file structure"
+            );
+        }
+
+        let mut tree = TechnologyTree {
+            technologies: vec![],
+        };
+        for obj in objects {
+            let technology = Technology::from_json_object(&obj, item_factory);
+            if tree.get(&technology.name).is_some() {
+                log::error!("Technology {} is declared more than once", technology.name);
+                continue;
+            }
+            tree.technologies.push(technology);
+        }
+
+        // Prerequisites are declared before, so that the tree has no cycles.
+        for (id, technology) in tree.technologies.iter().enumerate() {
+            for prerequisite in &technology.prerequisites {
+                if !tree.technologies[..id]
+                    .iter()
+                    .any(|other| &other.name == prerequisite)
+                {
+                    log::error!(
+                        "Technology {} requires {} that isn't declared before it",
+                        technology.name,
+                        prerequisite
+                    );
+                }
+            }
+            for building in &technology.buildings {
+                if !building_registry.contains(building) {
+                    log::error!(
+                        "Technology {} unlocks unknown building {}",
+                        technology.name,
+                        building
+                    );
+                }
+            }
+        }
+
+        log::info!("{} technologies are loaded", tree.technologies.len());
+        tree
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Technology> {
+        self.technologies
+            .iter()
+            .find(|technology| technology.name == name)
+    }
+
+    pub fn get_technologies(&self) -> &[Technology] {
+        &self.technologies
+    }
+}
+
+// Researched technologies and the items paid for the rest, kept in the Location.
+#[derive(Default)]
+pub struct Research {
+    // In order of researching.
+    researched: Vec<String>,
+    current: Option<String>,
+    // Amounts paid per cost entry, kept when the current technology is switched.
+    paid: HashMap<String, Vec<usize>>,
+}
+
+impl Research {
+    pub fn is_researched(&self, name: &str) -> bool {
+        self.researched.iter().any(|researched| researched == name)
+    }
+
+    pub fn get_current(&self) -> Option<&str> {
+        self.current.as_deref()
+    }
+
+    pub fn can_research(&self, technology: &Technology) -> bool {
+        !self.is_researched(&technology.name)
+            && technology
+                .prerequisites
+                .iter()
+                .all(|prerequisite| self.is_researched(prerequisite))
+    }
+
+    pub fn select(&mut self, tree: &TechnologyTree, name: Option<&str>) {
+        self.current = match name.and_then(|name| tree.get(name)) {
+            Some(technology) if self.can_research(technology) => Some(technology.name.clone()),
+            Some(technology) => {
+                log::info!("Technology {} can't be researched", technology.name);
+                return;
+            }
+            None => None,
+        };
+    }
+
+    pub fn get_paid(&self, technology: &Technology) -> Vec<usize> {
+        self.paid
+            .get(&technology.name)
+            .cloned()
+            .unwrap_or_else(|| vec![0; technology.cost.len()])
+    }
+
+    // Items left to pay for the current technology.
+    pub fn get_demand(&self, tree: &TechnologyTree) -> Vec<(Item, usize)> {
+        let technology = match self.current.as_deref().and_then(|name| tree.get(name)) {
+            Some(technology) => technology,
+            None => return vec![],
+        };
+        technology
+            .cost
+            .iter()
+            .zip(self.get_paid(technology))
+            .filter(|((_, amount), paid)| paid < amount)
+            .map(|((item, amount), paid)| (item.clone(), amount - paid))
+            .collect()
+    }
+
+    // Returns the amount accepted, the technology is researched once all the cost is paid.
+    pub fn pay(&mut self, tree: &TechnologyTree, item: &Item, amount: usize) -> usize {
+        let technology = match self.current.as_deref().and_then(|name| tree.get(name)) {
+            Some(technology) => technology,
+            None => return 0,
+        };
+        let mut paid = self.get_paid(technology);
+
+        let mut accepted = 0;
+        for ((cost_item, cost_amount), paid) in technology.cost.iter().zip(paid.iter_mut()) {
+            if cost_item.get_id() == item.get_id() {
+                let added = (amount - accepted).min(cost_amount - *paid);
+                *paid += added;
+                accepted += added;
+            }
+        }
+
+        if technology
+            .cost
+            .iter()
+            .zip(paid.iter())
+            .all(|((_, amount), paid)| paid >= amount)
+        {
+            log::info!("Technology {} is researched", technology.name);
+            self.paid.remove(&technology.name);
+            self.researched.push(technology.name.clone());
+            self.current = None;
+        } else {
+            self.paid.insert(technology.name.clone(), paid);
+        }
+        accepted
+    }
+
+    // Buildings and recipes not unlocked by any technology are available from the start.
+    pub fn is_building_unlocked(&self, tree: &TechnologyTree, name: &str) -> bool {
+        let unlocking: Vec<&Technology> = tree
+            .technologies
+            .iter()
+            .filter(|technology| technology.buildings.iter().any(|building| building == name))
+            .collect();
+        unlocking.is_empty()
+            || unlocking
+                .iter()
+                .any(|technology| self.is_researched(&technology.name))
+    }
+
+    pub fn get_locked_recipes(&self, tree: &TechnologyTree) -> Vec<String> {
+        let unlocked: Vec<&String> = tree
+            .technologies
+            .iter()
+            .filter(|technology| self.is_researched(&technology.name))
+            .flat_map(|technology| technology.recipes.iter())
+            .collect();
+        let mut locked: Vec<String> = tree
+            .technologies
+            .iter()
+            .flat_map(|technology| technology.recipes.iter())
+            .filter(|recipe| !unlocked.contains(recipe))
+            .cloned()
+            .collect();
+        locked.sort();
+        locked.dedup();
+        locked
+    }
+
+    pub fn save_state(&self) -> serde_json::Value {
+        let mut paid: Vec<(&String, &Vec<usize>)> = self.paid.iter().collect();
+        paid.sort();
+
+        json!({
+            "researched": self.researched,
+            "current": self.current,
+            "paid": paid
+                .into_iter()
+                .map(|(technology, amounts)| json!({ "technology": technology, "amounts": amounts }))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn from_saved_state(
+        state: &serde_json::Value,
+        tree: &TechnologyTree,
+        error: &mut bool,
+    ) -> Research {
+        let read_technology = |value: &serde_json::Value, error: &mut bool| match value
+            .as_str()
+            .and_then(|name| tree.get(name))
+        {
+            Some(technology) => Some(technology),
+            None => {
+                *error = true;
+                None
+            }
+        };
+
+        let researched = JsonReader::read_vec(state, "researched", error)
+            .iter()
+            .filter_map(|name| read_technology(name, error))
+            .map(|technology| technology.name.clone())
+            .collect();
+        let current = match state.get("current") {
+            None | Some(serde_json::Value::Null) => None,
+            Some(name) => read_technology(name, error).map(|technology| technology.name.clone()),
+        };
+
+        let mut paid = HashMap::new();
+        for saved in JsonReader::read_vec(state, "paid", error) {
+            let technology = match saved.get("technology") {
+                Some(name) => read_technology(name, error),
+                None => {
+                    *error = true;
+                    None
+                }
+            };
+            let amounts: Vec<usize> = JsonReader::read_vec(&saved, "amounts", error)
+                .iter()
+                .map(|amount| amount.as_u64().unwrap_or(0) as usize)
+                .collect();
+            match technology {
+                Some(technology) if technology.cost.len() == amounts.len() => {
+                    paid.insert(technology.name.clone(), amounts);
+                }
+                _ => *error = true,
+            }
+        }
+
+        Research {
+            researched,
+            current,
+            paid,
+        }
+    }
+}
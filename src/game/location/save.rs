@@ -0,0 +1,45 @@
+use crate::game::common::asset_manager::AssetManager;
+use crate::game::location::field::{
+    building::{building_registry::BuildingRegistry, item::ItemFactory},
+    cell::surface::SurfaceFactory,
+    fluid::FluidFactory,
+    world_generator::WorldGeneratorFactory,
+};
+use crate::game::location::research::TechnologyTree;
+
+// Everything needed to restore entities from a save or a scenario.
+pub struct LoadContext {
+    pub item_factory: ItemFactory,
+    pub surface_factory: SurfaceFactory,
+    pub building_registry: BuildingRegistry,
+    pub world_generator_factory: WorldGeneratorFactory,
+    pub technology_tree: TechnologyTree,
+}
+
+impl LoadContext {
+    pub fn new(asset_manager: &AssetManager) -> LoadContext {
+        let items_json = AssetManager::get_asset_id("dictionaries/items.json");
+        let item_factory = ItemFactory::new(asset_manager.get_json(items_json));
+
+        let fluids_json = AssetManager::get_asset_id("dictionaries/fluids.json");
+        let fluid_factory = FluidFactory::new(asset_manager.get_json(fluids_json));
+
+        let surfaces_json = AssetManager::get_asset_id("dictionaries/surfaces.json");
+        let surface_factory = SurfaceFactory::new(asset_manager.get_json(surfaces_json));
+
+        let building_registry = BuildingRegistry::new(asset_manager, &item_factory, &fluid_factory);
+        let technology_tree = TechnologyTree::new(asset_manager, &item_factory, &building_registry);
+
+        let generators_json = AssetManager::get_asset_id("dictionaries/world_generators.json");
+        let world_generator_factory =
+            WorldGeneratorFactory::new(asset_manager.get_json(generators_json), &surface_factory);
+
+        LoadContext {
+            item_factory,
+            surface_factory,
+            building_registry,
+            world_generator_factory,
+            technology_tree,
+        }
+    }
+}
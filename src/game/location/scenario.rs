@@ -0,0 +1,168 @@
+use crate::game::common::{asset_manager::AssetManager, json_reader::JsonReader, math::IVec2};
+use crate::game::location::{
+    field::{
+        building::facing_from_json_object,
+        cell::{surface::SurfaceFactory, Cell},
+        laying_object::LayingObject,
+        trigger::Trigger,
+        world_generator::WorldGenerator,
+        Field,
+    },
+    save::LoadContext,
+};
+
+pub const DEFAULT_SCENARIO: &str = "scenarios/debug.json";
+
+// Initial world description: initially loaded area, world generator, surfaces, buildings
+// (by dictionary name), laying objects, triggers and the player spawn.
+pub struct Scenario {
+    name: String,
+    json: serde_json::Value,
+}
+
+impl Scenario {
+    pub fn from_asset(asset_manager: &AssetManager, path: &str) -> Scenario {
+        let json = asset_manager.get_json(AssetManager::get_asset_id(path));
+        Self::from_str(path, json.as_ref())
+    }
+
+    // Scenarios outside of the assets dir can be changed without recompiling.
+    pub fn from_file(path: &str) -> Option<Scenario> {
+        match std::fs::read_to_string(path) {
+            Ok(json) => Some(Self::from_str(path, &json)),
+            Err(e) => {
+                log::error!("Failed to read scenario {} : {}", path, e);
+                None
+            }
+        }
+    }
+
+    fn from_str(name: &str, json: &str) -> Scenario {
+        let json = serde_json::from_str(json).unwrap_or_else(|e| {
+            log::error!("Scenario {} haven't been succesfully loaded : {}", name, e);
+            serde_json::Value::Object(serde_json::Map::new())
+        });
+
+        Scenario {
+            name: String::from(name),
+            json,
+        }
+    }
+
+    pub fn create_field(&self, context: &LoadContext) -> Field {
+        let mut error = false;
+
+        let mut field = Field::new(self.create_world_generator(context, &mut error));
+
+        // Field grows on demand, bounds only define the initially loaded area.
+        let bounds = JsonReader::read_obj(&self.json, "field", &mut error);
+        let min_coord = JsonReader::read_ivec2(&bounds, "min", &mut error);
+        let max_coord = JsonReader::read_ivec2(&bounds, "max", &mut error);
+        if error || min_coord.x > max_coord.x || min_coord.y > max_coord.y {
+            log::error!(
+                "Scenario {} haven't been succesfully loaded : wrong field bounds",
+                self.name
+            );
+            return field;
+        }
+
+        field.load_area(min_coord, max_coord);
+
+        self.fill_surfaces(&mut field, context, &mut error);
+        self.place_buildings(&mut field, context, &mut error);
+
+        for obj in JsonReader::read_vec(&self.json, "laying_objects", &mut error) {
+            field.add_laying_object(LayingObject::from_json_object(
+                &obj,
+                &context.item_factory,
+                &mut error,
+            ));
+        }
+
+        // Optional "triggers".
+        if self.json.get("triggers").is_some() {
+            for obj in JsonReader::read_vec(&self.json, "triggers", &mut error) {
+                field.add_trigger(Trigger::from_json_object(&obj, &mut error));
+            }
+        }
+
+        let player = JsonReader::read_obj(&self.json, "player", &mut error);
+        let spawn = JsonReader::read_vec2(&player, "position", &mut error);
+        field.player.body.set_position(spawn);
+
+        if error {
+            log::error!(
+                "Scenario {} haven't been succesfully loaded : wrong JSON file structure",
+                self.name
+            );
+        } else {
+            log::info!("Scenario succesfully loaded({})", self.name);
+        }
+
+        field
+    }
+
+    // "world_generator" with "name" from the dictionary and "seed",
+    // otherwise "surface" fills the whole field.
+    fn create_world_generator(&self, context: &LoadContext, error: &mut bool) -> WorldGenerator {
+        match self.json.get("world_generator") {
+            Some(generator) => {
+                let name = JsonReader::read_string(generator, "name", error);
+                let seed = JsonReader::read_u32(generator, "seed", error);
+                if !context.world_generator_factory.contains(&name) {
+                    *error = true;
+                }
+                context.world_generator_factory.create(&name, seed)
+            }
+            None => {
+                let surface_name = JsonReader::read_string(&self.json, "surface", error);
+                WorldGenerator::flat(
+                    context
+                        .surface_factory
+                        .create_surface(SurfaceFactory::get_surface_id_by_name(&surface_name)),
+                )
+            }
+        }
+    }
+
+    // "surfaces" override rectangular areas given by inclusive "min" and "max" cells.
+    fn fill_surfaces(&self, field: &mut Field, context: &LoadContext, error: &mut bool) {
+        for area in JsonReader::read_vec(&self.json, "surfaces", error) {
+            let min = JsonReader::read_ivec2(&area, "min", error);
+            let max = JsonReader::read_ivec2(&area, "max", error);
+            let surface_name = JsonReader::read_string(&area, "surface", error);
+            let surface = context
+                .surface_factory
+                .create_surface(SurfaceFactory::get_surface_id_by_name(&surface_name));
+
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    *field.get_or_load_cell_mut(IVec2::new(x, y)) = Cell::new(surface.clone());
+                }
+            }
+        }
+    }
+
+    fn place_buildings(&self, field: &mut Field, context: &LoadContext, error: &mut bool) {
+        for obj in JsonReader::read_vec(&self.json, "buildings", error) {
+            let name = JsonReader::read_string(&obj, "name", error);
+            let position = JsonReader::read_ivec2(&obj, "position", error);
+
+            if !context.building_registry.contains(&name) {
+                *error = true;
+            }
+
+            let mut building = context.building_registry.create(&name);
+            building.set_facing(facing_from_json_object(&obj, error));
+
+            if let Some(config) = obj.get("config") {
+                building.load_config(config, error);
+            }
+
+            if let Err(e) = field.build(position, building) {
+                log::error!("Building {} can't be placed at {} : {}", name, position, e);
+                *error = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use serde_json::json;
+
+use crate::game::{common::json_reader::JsonReader, gui::with_gui::*};
+
+use super::event::{EventBus, EventSubscriber, GameEvent, GameEventKind, SubscriberId};
+
+// Totals gathered from the gameplay events, kept in the save.
+pub struct Statistics {
+    subscriber: SubscriberId,
+
+    // By item name.
+    produced_items: BTreeMap<String, usize>,
+    recipes_completed: u32,
+    buildings_placed: u32,
+    buildings_removed: u32,
+    technologies_researched: u32,
+}
+
+impl Statistics {
+    pub fn new(event_bus: &mut EventBus) -> Statistics {
+        Statistics {
+            subscriber: event_bus.subscribe(&[
+                GameEventKind::ItemProduced,
+                GameEventKind::RecipeCompleted,
+                GameEventKind::BuildingPlaced,
+                GameEventKind::BuildingRemoved,
+                GameEventKind::TechnologyResearched,
+            ]),
+            produced_items: BTreeMap::new(),
+            recipes_completed: 0,
+            buildings_placed: 0,
+            buildings_removed: 0,
+            technologies_researched: 0,
+        }
+    }
+}
+
+impl EventSubscriber for Statistics {
+    fn get_name(&self) -> &str {
+        "statistics"
+    }
+
+    fn handle_events(&mut self, event_bus: &mut EventBus) {
+        for event in event_bus.take_events(self.subscriber) {
+            match event {
+                GameEvent::ItemProduced { item, amount, .. } => {
+                    *self
+                        .produced_items
+                        .entry(String::from(item.get_name()))
+                        .or_insert(0) += amount;
+                }
+                GameEvent::RecipeCompleted { .. } => self.recipes_completed += 1,
+                GameEvent::BuildingPlaced { .. } => self.buildings_placed += 1,
+                GameEvent::BuildingRemoved { .. } => self.buildings_removed += 1,
+                GameEvent::TechnologyResearched { .. } => self.technologies_researched += 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn save_state(&self) -> Option<serde_json::Value> {
+        Some(json!({
+            "produced_items": self
+                .produced_items
+                .iter()
+                .map(|(item, amount)| json!({ "item": item, "amount": amount }))
+                .collect::<Vec<_>>(),
+            "recipes_completed": self.recipes_completed,
+            "buildings_placed": self.buildings_placed,
+            "buildings_removed": self.buildings_removed,
+            "technologies_researched": self.technologies_researched,
+        }))
+    }
+
+    fn load_state(&mut self, state: &serde_json::Value, error: &mut bool) {
+        self.produced_items = JsonReader::read_vec(state, "produced_items", error)
+            .iter()
+            .map(|saved| {
+                (
+                    JsonReader::read_string(saved, "item", error),
+                    JsonReader::read_u32(saved, "amount", error) as usize,
+                )
+            })
+            .collect();
+        self.recipes_completed = JsonReader::read_u32(state, "recipes_completed", error);
+        self.buildings_placed = JsonReader::read_u32(state, "buildings_placed", error);
+        self.buildings_removed = JsonReader::read_u32(state, "buildings_removed", error);
+        self.technologies_researched =
+            JsonReader::read_u32(state, "technologies_researched", error);
+    }
+}
+
+impl WithGui for Statistics {
+    fn render_gui(&mut self, params: &mut GuiRenderParams) {
+        imgui::Window::new("statistics")
+            .size([200.0, 200.0], imgui::Condition::FirstUseEver)
+            .position([0.0, 570.0], imgui::Condition::FirstUseEver)
+            .build(&params.ui, || {
+                params.ui.text("produced:");
+                for (item, amount) in &self.produced_items {
+                    params.ui.text(format!("  {}: {}", item, amount));
+                }
+                params.ui.separator();
+                params
+                    .ui
+                    .text(format!("recipes completed: {}", self.recipes_completed));
+                params
+                    .ui
+                    .text(format!("buildings placed: {}", self.buildings_placed));
+                params
+                    .ui
+                    .text(format!("buildings removed: {}", self.buildings_removed));
+                params.ui.text(format!(
+                    "technologies researched: {}",
+                    self.technologies_researched
+                ));
+            });
+    }
+}
@@ -1,201 +1,287 @@
-use ggez::event::{EventHandler, KeyCode, KeyMods, MouseButton};
-use ggez::graphics::{self, Color};
-use ggez::{Context, GameResult};
-
-use common::asset_manager::AssetManager;
-use common::math::{IVec2, Vec2};
-
-pub mod common;
-pub mod game_entity;
-pub mod gui;
-pub mod location;
-pub mod message;
-pub mod renderer;
-
-use game_entity::*;
-use gui::Gui;
-use location::{
-    physics_scene::{PhysicsScene, PhysicsSimulated},
-    Location,
-};
-use renderer::{camera::Camera, Renderer};
-
-use self::gui::with_gui::WithGui;
-
-pub const TICK_PERIOD: f32 = 1.0;
-
-pub struct Game {
-    gui: Gui,
-    renderer: Renderer,
-    asset_manager: AssetManager,
-    location: Location,
-
-    from_last_tick: f32,
-    tick_id: u32,
-    frame_time: f32,
-    avg_frame_time: f32,
-    frames_times_collected: u32,
-}
-
-impl Game {
-    pub fn new(context: &mut Context) -> Game {
-        let mut asset_manager = AssetManager::new();
-
-        asset_manager.load_assets(context);
-
-        let drawable_size = graphics::drawable_size(context);
-        let res = IVec2::new(drawable_size.0 as isize, drawable_size.1 as isize);
-        let camera = Camera::new(res);
-        let renderer = Renderer::new(camera);
-
-        let location = Location::new(&asset_manager);
-
-        Game {
-            gui: Gui::new(context),
-            location,
-            asset_manager,
-            renderer,
-
-            from_last_tick: 0.0,
-            tick_id: 0,
-            frame_time: 0.0,
-            avg_frame_time: 0.0,
-            frames_times_collected: 0,
-        }
-    }
-
-    fn update_all(&mut self, parameters: &UpdateParameters) {
-        self.location.update(parameters);
-    }
-
-    fn tick_all(&mut self) {
-        self.location.tick(self.tick_id);
-    }
-
-    fn render_all(&mut self) {
-        let transform = SpriteTransform::default();
-        self.location.render(&mut self.renderer, transform.clone());
-    }
-}
-
-impl EventHandler for Game {
-    fn update(&mut self, context: &mut Context) -> GameResult<()> {
-        let delta_time = ggez::timer::delta(context).as_secs_f32();
-
-        self.frame_time += delta_time;
-        self.frames_times_collected += 1;
-
-        self.from_last_tick += delta_time;
-        if self.from_last_tick > TICK_PERIOD {
-            self.from_last_tick -= TICK_PERIOD;
-            self.tick_all();
-            self.tick_id += 1;
-
-            self.avg_frame_time = self.frame_time / (self.frames_times_collected as f32);
-            self.frame_time = 0.0;
-            self.frames_times_collected = 0;
-        }
-
-        let update_parameters = UpdateParameters {
-            delta_time,
-            from_last_tick: self.from_last_tick,
-            last_tick_id: self.tick_id,
-        };
-
-        self.location.process_keyboard_input(context);
-
-        for _ in 0..2 {
-            let hierarchy = self.location.get_bodies();
-            let mut scene = PhysicsScene::new(hierarchy);
-            let messages = scene.simulate(delta_time / 2.0);
-            self.location.handle_physics_messages(messages);
-            self.location.physics_update(delta_time / 2.0);
-        }
-
-        self.update_all(&update_parameters);
-
-        Ok(())
-    }
-
-    fn draw(&mut self, context: &mut Context) -> GameResult<()> {
-        graphics::clear(context, Color::WHITE);
-
-        let camera_properties = self.location.get_camera_properties();
-        self.renderer.set_camera_properties(camera_properties);
-
-        self.render_all();
-        self.renderer.render_to_screen(context, &self.asset_manager);
-        self.gui
-            .render(context, &self.asset_manager, 1.0, |mut params| {
-                self.location.render_gui(&mut params);
-
-                imgui::Window::new("debug info")
-                    .size([200.0, 100.0], imgui::Condition::Always)
-                    .position_pivot([1.0, 0.0])
-                    .position([params.screen_size.x, 0.0], imgui::Condition::Always)
-                    .flags(imgui::WindowFlags::NO_RESIZE | imgui::WindowFlags::NO_COLLAPSE)
-                    .build(&params.ui, || {
-                        params.ui.text(format!("tick id: {}", self.tick_id));
-                        params
-                            .ui
-                            .text(format!("from last tick: {}", self.from_last_tick));
-                        params
-                            .ui
-                            .text(format!("avg frame time: {}", self.avg_frame_time));
-                        params
-                            .ui
-                            .text(format!("avg fps: {}", 1.0 / self.avg_frame_time));
-                    });
-            });
-
-        graphics::present(context)
-    }
-
-    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
-        self.gui.update_mouse_pos(x, y);
-    }
-
-    fn mouse_button_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        button: MouseButton,
-        _x: f32,
-        _y: f32,
-    ) {
-        self.gui.update_mouse_down(button);
-    }
-
-    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
-        self.gui.update_mouse_up(button);
-    }
-
-    fn key_down_event(
-        &mut self,
-        _ctx: &mut Context,
-        keycode: KeyCode,
-        keymods: KeyMods,
-        _repeat: bool,
-    ) {
-        self.gui.update_key_down(keycode, keymods);
-    }
-
-    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
-        self.gui.update_key_up(keycode, keymods);
-    }
-
-    fn text_input_event(&mut self, _ctx: &mut Context, val: char) {
-        self.gui.update_text(val);
-    }
-
-    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
-        self.renderer
-            .set_resolution(IVec2::new(width as isize, height as isize));
-
-        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, width, height))
-            .unwrap();
-    }
-
-    fn mouse_wheel_event(&mut self, _ctx: &mut Context, x: f32, y: f32) {
-        self.gui.update_scroll(x, y);
-    }
-}
+use ggez::event::{EventHandler, KeyCode, KeyMods, MouseButton};
+use ggez::graphics::{self, Color};
+use ggez::input::keyboard;
+use ggez::{Context, GameResult};
+
+use common::asset_manager::AssetManager;
+use common::math::{IVec2, Vec2};
+
+pub mod common;
+pub mod game_entity;
+pub mod gui;
+pub mod location;
+pub mod message;
+pub mod renderer;
+pub mod scheduler;
+pub mod simulation;
+
+use game_entity::*;
+use gui::Gui;
+use location::scenario::{Scenario, DEFAULT_SCENARIO};
+use renderer::{camera::Camera, Renderer};
+use scheduler::{Scheduler, SimulationSpeed};
+use simulation::Simulation;
+
+use self::gui::with_gui::WithGui;
+
+pub const SAVE_FILE_PATH: &str = "save.json";
+
+pub struct Game {
+    gui: Gui,
+    renderer: Renderer,
+    asset_manager: AssetManager,
+    simulation: Simulation,
+    // Kept when the simulation is loaded.
+    scheduler: Scheduler,
+
+    // In screen coordinates.
+    mouse_position: Vec2,
+
+    frame_time: f32,
+    avg_frame_time: f32,
+    frames_times_collected: u32,
+}
+
+impl Game {
+    // Scenario file is read from the disk if provided, default one is used otherwise.
+    pub fn new(context: &mut Context, scenario_path: Option<&str>, scheduler: Scheduler) -> Game {
+        let mut asset_manager = AssetManager::new();
+
+        asset_manager.load_assets(context);
+
+        let drawable_size = graphics::drawable_size(context);
+        let res = IVec2::new(drawable_size.0 as isize, drawable_size.1 as isize);
+        let camera = Camera::new(res);
+        let renderer = Renderer::new(camera);
+
+        let scenario = scenario_path
+            .and_then(Scenario::from_file)
+            .unwrap_or_else(|| Scenario::from_asset(&asset_manager, DEFAULT_SCENARIO));
+        let simulation = Simulation::new(&asset_manager, &scenario);
+
+        Game {
+            gui: Gui::new(context),
+            simulation,
+            scheduler,
+            asset_manager,
+            renderer,
+
+            mouse_position: Vec2::zero(),
+
+            frame_time: 0.0,
+            avg_frame_time: 0.0,
+            frames_times_collected: 0,
+        }
+    }
+
+    fn process_keyboard_input(&mut self, context: &Context) {
+        let mut dir = Vec2::zero();
+
+        if keyboard::is_key_pressed(context, KeyCode::A) {
+            dir = dir + Vec2::new(-1.0, 0.0);
+        }
+        if keyboard::is_key_pressed(context, KeyCode::D) {
+            dir = dir + Vec2::new(1.0, 0.0);
+        }
+        if keyboard::is_key_pressed(context, KeyCode::W) {
+            dir = dir + Vec2::new(0.0, 1.0);
+        }
+        if keyboard::is_key_pressed(context, KeyCode::S) {
+            dir = dir + Vec2::new(0.0, -1.0);
+        }
+
+        self.simulation
+            .get_location_mut()
+            .set_player_movement_direction(dir);
+    }
+
+    fn save(&self) {
+        match std::fs::write(SAVE_FILE_PATH, self.simulation.save()) {
+            Ok(()) => log::info!("Saved to {}", SAVE_FILE_PATH),
+            Err(e) => log::error!("Failed to write {} : {}", SAVE_FILE_PATH, e),
+        }
+    }
+
+    fn load(&mut self) {
+        let save = match std::fs::read_to_string(SAVE_FILE_PATH) {
+            Ok(save) => save,
+            Err(e) => {
+                log::error!("Failed to read {} : {}", SAVE_FILE_PATH, e);
+                return;
+            }
+        };
+
+        if let Some(simulation) = Simulation::load(&self.asset_manager, &save) {
+            self.simulation = simulation;
+        }
+    }
+
+    fn render_all(&mut self) {
+        let transform = SpriteTransform::default();
+        self.simulation
+            .get_location_mut()
+            .render(&mut self.renderer, transform.clone());
+    }
+}
+
+impl EventHandler for Game {
+    fn update(&mut self, context: &mut Context) -> GameResult<()> {
+        let delta_time = ggez::timer::delta(context).as_secs_f32();
+
+        self.frame_time += delta_time;
+        self.frames_times_collected += 1;
+
+        self.process_keyboard_input(context);
+
+        let prev_tick_id = self.simulation.get_tick_id();
+        self.simulation.update(&mut self.scheduler, delta_time);
+
+        if self.simulation.get_tick_id() != prev_tick_id {
+            self.avg_frame_time = self.frame_time / (self.frames_times_collected as f32);
+            self.frame_time = 0.0;
+            self.frames_times_collected = 0;
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, context: &mut Context) -> GameResult<()> {
+        graphics::clear(context, Color::WHITE);
+
+        let camera_properties = self.simulation.get_location().get_camera_properties();
+        self.renderer.set_camera_properties(camera_properties);
+        // Camera follows the player, so the cursor moves in the world even if the mouse doesn't.
+        let cursor_position = self.renderer.screen_to_world_space(self.mouse_position);
+        self.simulation
+            .get_location_mut()
+            .set_cursor_position(cursor_position);
+
+        self.render_all();
+        self.renderer.render_to_screen(context, &self.asset_manager);
+        self.gui
+            .render(context, &self.asset_manager, 1.0, |mut params| {
+                self.simulation.get_location_mut().render_gui(&mut params);
+
+                let scheduler = &mut self.scheduler;
+                imgui::Window::new("debug info")
+                    .size([200.0, 190.0], imgui::Condition::Always)
+                    .position_pivot([1.0, 0.0])
+                    .position([params.screen_size.x, 0.0], imgui::Condition::Always)
+                    .flags(imgui::WindowFlags::NO_RESIZE | imgui::WindowFlags::NO_COLLAPSE)
+                    .build(&params.ui, || {
+                        params
+                            .ui
+                            .text(format!("tick id: {}", self.simulation.get_tick_id()));
+                        params.ui.text(format!(
+                            "from last tick: {:.2}",
+                            self.simulation.get_from_last_tick(scheduler)
+                        ));
+                        params
+                            .ui
+                            .text(format!("avg frame time: {}", self.avg_frame_time));
+                        params
+                            .ui
+                            .text(format!("avg fps: {}", 1.0 / self.avg_frame_time));
+                        params
+                            .ui
+                            .text(format!("lagged frames: {}", scheduler.get_lagged_frames()));
+
+                        params.ui.separator();
+                        let paused = scheduler.is_paused();
+                        if params.ui.button(if paused { "resume" } else { "pause" }) {
+                            scheduler.set_paused(!paused);
+                        }
+                        if paused {
+                            params.ui.same_line();
+                            if params.ui.button("step") {
+                                scheduler.request_step();
+                            }
+                        }
+                        for speed in SimulationSpeed::ALL {
+                            if params
+                                .ui
+                                .radio_button_bool(speed.get_name(), scheduler.get_speed() == speed)
+                            {
+                                scheduler.set_speed(speed);
+                            }
+                            params.ui.same_line();
+                        }
+                        params.ui.new_line();
+                    });
+            });
+
+        graphics::present(context)
+    }
+
+    fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.gui.update_mouse_pos(x, y);
+        self.mouse_position = Vec2::new(x, y);
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        _x: f32,
+        _y: f32,
+    ) {
+        self.gui.update_mouse_down(button);
+
+        if self.gui.wants_mouse() {
+            return;
+        }
+        let location = self.simulation.get_location_mut();
+        match button {
+            MouseButton::Left => location.click(),
+            MouseButton::Right => location.deconstruct_building(),
+            _ => {}
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        self.gui.update_mouse_up(button);
+    }
+
+    fn key_down_event(
+        &mut self,
+        _ctx: &mut Context,
+        keycode: KeyCode,
+        keymods: KeyMods,
+        repeat: bool,
+    ) {
+        self.gui.update_key_down(keycode, keymods);
+
+        if !repeat && !self.gui.wants_keyboard() {
+            match keycode {
+                KeyCode::R => self.simulation.get_location_mut().rotate_building(),
+                KeyCode::Q => self
+                    .simulation
+                    .get_location_mut()
+                    .select_building_to_place(None),
+                KeyCode::F5 => self.save(),
+                KeyCode::F9 => self.load(),
+                _ => {}
+            }
+        }
+    }
+
+    fn key_up_event(&mut self, _ctx: &mut Context, keycode: KeyCode, keymods: KeyMods) {
+        self.gui.update_key_up(keycode, keymods);
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, val: char) {
+        self.gui.update_text(val);
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        self.renderer
+            .set_resolution(IVec2::new(width as isize, height as isize));
+
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(0.0, 0.0, width, height))
+            .unwrap();
+    }
+
+    fn mouse_wheel_event(&mut self, _ctx: &mut Context, x: f32, y: f32) {
+        self.gui.update_scroll(x, y);
+    }
+}
@@ -56,6 +56,13 @@ impl Camera {
         res
     }
 
+    pub fn screen_to_world_space(&self, screen: Vec2) -> Vec2 {
+        let mut screen = screen;
+        screen.y = self.resolution.y as f32 - screen.y;
+        (screen - self.resolution.to_vec2() * 0.5) / self.properties.cell_size
+            + self.properties.offset
+    }
+
     pub fn get_bounds(&self) -> Rect {
         let resolution_cells = self.resolution.to_vec2() / self.properties.cell_size;
         let mut bounds = Rect::zero();
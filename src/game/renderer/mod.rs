@@ -1,5 +1,8 @@
 use ggez::mint::{Point2, Vector2};
-use ggez::{graphics, Context};
+use ggez::{
+    graphics::{self, Color},
+    Context,
+};
 
 use crate::game::common::asset_manager::AssetManager;
 use crate::game::common::math::{Rect, Vec2};
@@ -39,6 +42,10 @@ impl Renderer {
         self.queued_sprites.push((sprite, transform));
     }
 
+    pub fn screen_to_world_space(&self, screen: Vec2) -> Vec2 {
+        self.camera.screen_to_world_space(screen)
+    }
+
     pub fn get_render_bounds(&self) -> Rect {
         self.camera.get_bounds()
     }
@@ -67,7 +74,8 @@ impl Renderer {
                     .dest(dest)
                     .rotation(rotation)
                     .offset(offset)
-                    .scale(scale),
+                    .scale(scale)
+                    .color(Color::new(1.0, 1.0, 1.0, transform.alpha)),
             )
             .unwrap();
         }
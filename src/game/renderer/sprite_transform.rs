@@ -5,6 +5,8 @@ pub struct SpriteTransform {
     pub translation: Vec2,
     pub rotation: f32,
     pub scale: Vec2,
+    // Opacity in [0, 1], e.g. for previews.
+    pub alpha: f32,
 }
 
 impl SpriteTransform {
@@ -13,6 +15,7 @@ impl SpriteTransform {
             translation: Vec2::zero(),
             rotation: 0.0,
             scale: Vec2::new_xy(1.0),
+            alpha: 1.0,
         }
     }
 
@@ -31,11 +34,17 @@ impl SpriteTransform {
         self
     }
 
+    pub fn add_alpha(mut self, alpha: f32) -> SpriteTransform {
+        self.alpha *= alpha;
+        self
+    }
+
     pub fn combine(&self, other: &SpriteTransform) -> SpriteTransform {
         SpriteTransform {
             translation: self.translation + other.translation,
             rotation: self.rotation + other.rotation,
             scale: self.scale * other.scale,
+            alpha: self.alpha * other.alpha,
         }
     }
 
@@ -44,6 +53,7 @@ impl SpriteTransform {
             scale: Vec2::new_xy(1.0) / self.scale,
             rotation: -self.rotation,
             translation: -1.0 * self.translation,
+            alpha: 1.0 / self.alpha,
         }
     }
 
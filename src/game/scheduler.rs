@@ -0,0 +1,199 @@
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TICK_RATE: f32 = 1.0;
+pub const DEFAULT_PHYSICS_RATE: f32 = 60.0;
+
+// Frame time above that is treated as a lag, the rest isn't caught up.
+const MAX_FRAME_TIME: f32 = 0.25;
+// At normal speed, multiplied by the speed.
+const MAX_STEPS_PER_FRAME: u32 = 8;
+// Time to simulate per frame at max speed, the rest of the frame is left to the rendering.
+const MAX_SPEED_FRAME_BUDGET: Duration = Duration::from_millis(12);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SimulationSpeed {
+    Normal,
+    Double,
+    Quadruple,
+    // As many steps as fit into the frame budget.
+    Max,
+}
+
+impl SimulationSpeed {
+    pub const ALL: [SimulationSpeed; 4] = [
+        SimulationSpeed::Normal,
+        SimulationSpeed::Double,
+        SimulationSpeed::Quadruple,
+        SimulationSpeed::Max,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SimulationSpeed::Normal => "1x",
+            SimulationSpeed::Double => "2x",
+            SimulationSpeed::Quadruple => "4x",
+            SimulationSpeed::Max => "max",
+        }
+    }
+
+    fn get_multiplier(&self) -> Option<u32> {
+        match self {
+            SimulationSpeed::Normal => Some(1),
+            SimulationSpeed::Double => Some(2),
+            SimulationSpeed::Quadruple => Some(4),
+            SimulationSpeed::Max => None,
+        }
+    }
+}
+
+// Fixed-step clock, turns the frame time into the physics steps to run.
+// Ticks happen every steps_per_tick physics steps, so both only depend on the step count.
+#[derive(Clone)]
+pub struct Scheduler {
+    // In seconds.
+    physics_period: f32,
+    steps_per_tick: u32,
+
+    speed: SimulationSpeed,
+    paused: bool,
+    // Runs the steps up to the next tick while paused.
+    step_requested: bool,
+
+    // Scaled by the speed, not simulated yet.
+    accumulator: f32,
+    steps_left: u32,
+    frame_start: Instant,
+    // Frames that had more time to catch up than allowed.
+    lagged_frames: u32,
+}
+
+impl Scheduler {
+    // Rates are per second, tick rate is rounded to a whole amount of physics steps.
+    pub fn new(tick_rate: f32, physics_rate: f32) -> Scheduler {
+        let (tick_rate, physics_rate) = if tick_rate > 0.0 && physics_rate >= tick_rate {
+            (tick_rate, physics_rate)
+        } else {
+            log::error!(
+                "Wrong scheduler rates {}/{}, defaults are used",
+                tick_rate,
+                physics_rate
+            );
+            (DEFAULT_TICK_RATE, DEFAULT_PHYSICS_RATE)
+        };
+
+        Scheduler {
+            physics_period: 1.0 / physics_rate,
+            steps_per_tick: (physics_rate / tick_rate).round() as u32,
+            speed: SimulationSpeed::Normal,
+            paused: false,
+            step_requested: false,
+            accumulator: 0.0,
+            steps_left: 0,
+            frame_start: Instant::now(),
+            lagged_frames: 0,
+        }
+    }
+
+    pub fn get_physics_period(&self) -> f32 {
+        self.physics_period
+    }
+
+    pub fn get_steps_per_tick(&self) -> u32 {
+        self.steps_per_tick
+    }
+
+    pub fn get_tick_period(&self) -> f32 {
+        self.physics_period * self.steps_per_tick as f32
+    }
+
+    // Not simulated part of the current physics step, for the interpolation.
+    pub fn get_accumulated_time(&self) -> f32 {
+        self.accumulator
+    }
+
+    pub fn get_speed(&self) -> SimulationSpeed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: SimulationSpeed) {
+        self.speed = speed;
+        self.accumulator = 0.0;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.step_requested = false;
+        self.accumulator = 0.0;
+    }
+
+    // Advances to the next tick, only while paused.
+    pub fn request_step(&mut self) {
+        if self.paused {
+            self.step_requested = true;
+        }
+    }
+
+    pub fn get_lagged_frames(&self) -> u32 {
+        self.lagged_frames
+    }
+
+    pub fn begin_frame(&mut self, delta_time: f32) {
+        self.frame_start = Instant::now();
+        if self.paused {
+            self.steps_left = if self.step_requested { u32::MAX } else { 0 };
+            return;
+        }
+
+        let multiplier = match self.speed.get_multiplier() {
+            Some(multiplier) => multiplier,
+            None => {
+                self.steps_left = u32::MAX;
+                return;
+            }
+        };
+
+        if delta_time > MAX_FRAME_TIME {
+            self.lagged_frames += 1;
+        }
+        self.accumulator += delta_time.min(MAX_FRAME_TIME) * multiplier as f32;
+        let steps = (self.accumulator / self.physics_period) as u32;
+        let max_steps = MAX_STEPS_PER_FRAME * multiplier;
+        if steps > max_steps {
+            // Time that can't be caught up is dropped instead of piling up.
+            self.lagged_frames += 1;
+            self.steps_left = max_steps;
+            self.accumulator = 0.0;
+        } else {
+            self.steps_left = steps;
+            self.accumulator -= steps as f32 * self.physics_period;
+        }
+    }
+
+    // Whether to run one more physics step this frame.
+    pub fn next_step(&mut self) -> bool {
+        if self.steps_left == 0 {
+            return false;
+        }
+        if self.speed == SimulationSpeed::Max
+            && !self.paused
+            && self.frame_start.elapsed() > MAX_SPEED_FRAME_BUDGET
+        {
+            return false;
+        }
+
+        self.steps_left -= 1;
+        true
+    }
+
+    // Called after each tick, ends the single step.
+    pub fn on_tick(&mut self) {
+        if self.paused && self.step_requested {
+            self.step_requested = false;
+            self.steps_left = 0;
+        }
+    }
+}
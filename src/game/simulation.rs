@@ -0,0 +1,136 @@
+use serde_json::json;
+
+use crate::game::common::{asset_manager::AssetManager, json_reader::JsonReader};
+use crate::game::game_entity::*;
+use crate::game::location::{
+    physics_scene::{PhysicsScene, PhysicsSimulated},
+    scenario::Scenario,
+    Location,
+};
+use crate::game::scheduler::Scheduler;
+
+// Increment when saved state layout changes.
+pub const SAVE_FORMAT_VERSION: u32 = 10;
+
+// Owns the world and advances it in time.
+// Doesn't depend on a window or a graphics context, so it can be run headless.
+pub struct Simulation {
+    location: Location,
+
+    // Physics steps since the last tick, the step count is saved so that the ticks
+    // happen at the same steps after loading.
+    steps_from_last_tick: u32,
+    tick_id: u32,
+}
+
+impl Simulation {
+    pub fn new(asset_manager: &AssetManager, scenario: &Scenario) -> Simulation {
+        Simulation {
+            location: Location::from_scenario(scenario, asset_manager),
+
+            steps_from_last_tick: 0,
+            tick_id: 0,
+        }
+    }
+
+    pub fn save(&self) -> String {
+        let state = json!({
+            "version": SAVE_FORMAT_VERSION,
+            "tick_id": self.tick_id,
+            "steps_from_last_tick": self.steps_from_last_tick,
+            "location": self.location.save_state(),
+        });
+        serde_json::to_string_pretty(&state).unwrap()
+    }
+
+    pub fn load(asset_manager: &AssetManager, save: &str) -> Option<Simulation> {
+        let state: serde_json::Value = match serde_json::from_str(save) {
+            Ok(state) => state,
+            Err(e) => {
+                log::error!("Save haven't been succesfully loaded : {}", e);
+                return None;
+            }
+        };
+
+        let mut error = false;
+        let version = JsonReader::read_u32(&state, "version", &mut error);
+        if version != SAVE_FORMAT_VERSION {
+            log::error!(
+                "Save haven't been succesfully loaded : unsupported format version {}(expected {})",
+                version,
+                SAVE_FORMAT_VERSION
+            );
+            return None;
+        }
+
+        let location_state = JsonReader::read_obj(&state, "location", &mut error);
+        let simulation = Simulation {
+            location: Location::from_saved_state(&location_state, asset_manager, &mut error),
+
+            steps_from_last_tick: JsonReader::read_u32(&state, "steps_from_last_tick", &mut error),
+            tick_id: JsonReader::read_u32(&state, "tick_id", &mut error),
+        };
+
+        if error {
+            log::error!("Save haven't been succesfully loaded : wrong save file structure");
+            return None;
+        }
+
+        Some(simulation)
+    }
+
+    pub fn get_location(&self) -> &Location {
+        &self.location
+    }
+
+    pub fn get_location_mut(&mut self) -> &mut Location {
+        &mut self.location
+    }
+
+    pub fn get_tick_id(&self) -> u32 {
+        self.tick_id
+    }
+
+    // In seconds of the simulated time.
+    pub fn get_from_last_tick(&self, scheduler: &Scheduler) -> f32 {
+        self.steps_from_last_tick as f32 * scheduler.get_physics_period()
+            + scheduler.get_accumulated_time()
+    }
+
+    // Advances simulation by delta_time seconds: runs the fixed physics steps the scheduler
+    // gives, ticking every steps_per_tick of them, then per-frame updates.
+    pub fn update(&mut self, scheduler: &mut Scheduler, delta_time: f32) {
+        scheduler.begin_frame(delta_time);
+        while scheduler.next_step() {
+            self.physics_step(scheduler.get_physics_period());
+
+            self.steps_from_last_tick += 1;
+            if self.steps_from_last_tick >= scheduler.get_steps_per_tick() {
+                self.steps_from_last_tick = 0;
+                self.tick();
+                scheduler.on_tick();
+            }
+        }
+
+        let update_parameters = UpdateParameters {
+            delta_time,
+            from_last_tick: self.get_from_last_tick(scheduler),
+            tick_period: scheduler.get_tick_period(),
+            last_tick_id: self.tick_id,
+        };
+        self.location.update(&update_parameters);
+    }
+
+    pub fn tick(&mut self) {
+        self.location.tick(self.tick_id);
+        self.tick_id += 1;
+    }
+
+    pub fn physics_step(&mut self, delta_time: f32) {
+        let hierarchy = self.location.get_bodies();
+        let mut scene = PhysicsScene::new(hierarchy);
+        let messages = scene.simulate(delta_time);
+        self.location.handle_physics_messages(messages);
+        self.location.physics_update(delta_time);
+    }
+}
@@ -5,11 +5,65 @@ use ggez::{event, ContextBuilder};
 
 mod game;
 
+use game::common::asset_manager::AssetManager;
+use game::location::scenario::{Scenario, DEFAULT_SCENARIO};
+use game::scheduler::{Scheduler, DEFAULT_PHYSICS_RATE, DEFAULT_TICK_RATE};
+use game::simulation::Simulation;
 use game::Game;
 
+const HEADLESS_DEFAULT_TICK_COUNT: u32 = 100;
+
 fn main() {
     game::common::logger::init().unwrap();
 
+    // Usage: rogue_box [--scenario <path>] [--headless [--ticks <count>] [--check-determinism]]
+    //     [--tick-rate <per second>] [--physics-rate <per second>]
+    let mut scenario_path = None;
+    let mut headless = false;
+    let mut check_determinism = false;
+    let mut tick_count = HEADLESS_DEFAULT_TICK_COUNT;
+    let mut tick_rate = DEFAULT_TICK_RATE;
+    let mut physics_rate = DEFAULT_PHYSICS_RATE;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--scenario" => scenario_path = args.next(),
+            "--headless" => headless = true,
+            "--check-determinism" => check_determinism = true,
+            "--ticks" => {
+                tick_count = args
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or(HEADLESS_DEFAULT_TICK_COUNT)
+            }
+            "--tick-rate" => {
+                tick_rate = args
+                    .next()
+                    .and_then(|rate| rate.parse().ok())
+                    .unwrap_or(DEFAULT_TICK_RATE)
+            }
+            "--physics-rate" => {
+                physics_rate = args
+                    .next()
+                    .and_then(|rate| rate.parse().ok())
+                    .unwrap_or(DEFAULT_PHYSICS_RATE)
+            }
+            _ => log::error!("Unknown argument {}", arg),
+        }
+    }
+
+    let scheduler = Scheduler::new(tick_rate, physics_rate);
+    if headless {
+        run_headless(
+            scenario_path.as_deref(),
+            tick_count,
+            check_determinism,
+            scheduler,
+        );
+        return;
+    }
+
     let window_setup = WindowSetup::default().title("");
 
     let (mut ctx, event_loop) = ContextBuilder::new("", "mertwole")
@@ -17,7 +71,126 @@ fn main() {
         .build()
         .expect("could not create ggez context!");
 
-    let game = Game::new(&mut ctx);
+    let game = Game::new(&mut ctx, scenario_path.as_deref(), scheduler);
 
     event::run(ctx, event_loop, game);
 }
+
+// Determinism check runs the scenario again, then once more with a save and load halfway,
+// all the final saves must be byte-identical.
+fn run_headless(
+    scenario_path: Option<&str>,
+    tick_count: u32,
+    check_determinism: bool,
+    scheduler: Scheduler,
+) {
+    let mut asset_manager = AssetManager::new();
+    asset_manager.load_json_assets();
+
+    let scenario = scenario_path
+        .and_then(Scenario::from_file)
+        .unwrap_or_else(|| Scenario::from_asset(&asset_manager, DEFAULT_SCENARIO));
+
+    let run = |reload_tick| {
+        simulate(
+            &asset_manager,
+            &scenario,
+            tick_count,
+            reload_tick,
+            scheduler.clone(),
+        )
+        .unwrap_or_else(|e| {
+            log::error!("{}", e);
+            std::process::exit(1);
+        })
+    };
+
+    let save = run(None);
+    println!("Simulated {} ticks", tick_count);
+    if !check_determinism {
+        return;
+    }
+
+    let rerun_save = run(None);
+    if rerun_save != save {
+        log::error!("Determinism check failed : rerun ended in a different state");
+        std::process::exit(1);
+    }
+    let reload_tick = tick_count / 2;
+    let reloaded_save = run(Some(reload_tick));
+    if reloaded_save != save {
+        log::error!(
+            "Determinism check failed : run reloaded at tick {} ended in a different state",
+            reload_tick
+        );
+        std::process::exit(1);
+    }
+    println!("Determinism check passed");
+}
+
+// One physics step per frame, so the run doesn't depend on the machine speed.
+// Simulation is reloaded once, at the first frame of the reload tick.
+// Returns the save of the final state.
+fn simulate(
+    asset_manager: &AssetManager,
+    scenario: &Scenario,
+    tick_count: u32,
+    mut reload_tick: Option<u32>,
+    mut scheduler: Scheduler,
+) -> Result<String, String> {
+    let mut simulation = Simulation::new(asset_manager, scenario);
+    let frame_time = scheduler.get_physics_period();
+    while simulation.get_tick_id() < tick_count {
+        if reload_tick == Some(simulation.get_tick_id()) {
+            reload_tick = None;
+            simulation = Simulation::load(asset_manager, &simulation.save()).ok_or_else(|| {
+                format!(
+                    "Simulation can't be reloaded at tick {}",
+                    simulation.get_tick_id()
+                )
+            })?;
+        }
+        simulation.update(&mut scheduler, frame_time);
+    }
+    Ok(simulation.save())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Short run of the default scenario, still several physics steps per tick.
+    const TICK_COUNT: u32 = 10;
+    const PHYSICS_RATE: f32 = DEFAULT_TICK_RATE * 4.0;
+
+    fn simulate_default_scenario(reload_tick: Option<u32>) -> String {
+        let mut asset_manager = AssetManager::new();
+        asset_manager.load_json_assets();
+        let scenario = Scenario::from_asset(&asset_manager, DEFAULT_SCENARIO);
+
+        simulate(
+            &asset_manager,
+            &scenario,
+            TICK_COUNT,
+            reload_tick,
+            Scheduler::new(DEFAULT_TICK_RATE, PHYSICS_RATE),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn rerun_is_byte_identical() {
+        assert_eq!(
+            simulate_default_scenario(None),
+            simulate_default_scenario(None)
+        );
+    }
+
+    #[test]
+    fn reloaded_run_is_byte_identical() {
+        assert_eq!(
+            simulate_default_scenario(None),
+            simulate_default_scenario(Some(TICK_COUNT / 2))
+        );
+    }
+}